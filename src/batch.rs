@@ -0,0 +1,121 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::params::check_structured;
+use crate::{JsonRpcError, Params, RpcId};
+
+/// Calls sent together with `Client::send_batch`
+///
+/// Results are returned in the order calls were added, whatever order
+/// the server answers in. With `max_batch_size` set, large batches are
+/// split into several HTTP requests of at most that many calls.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # async_std::task::block_on(async {
+/// use jsonrpc_v2_client::{Batch, Client, Params, ServiceAddress};
+///
+/// let client = Client::new(ServiceAddress::new("127.0.0.1:8082", "/api"));
+/// let mut batch = Batch::new().with_max_batch_size(100);
+/// let sum = batch.add("add", Params([1, 2])).unwrap();
+/// let product = batch.add("mul", Params([3, 4])).unwrap();
+///
+/// let results = client.send_batch(batch).await.unwrap();
+/// println!("{:?} {:?}", results[sum], results[product]);
+/// # });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Batch {
+    pub(crate) calls: Vec<(String, Value)>,
+    pub(crate) max_batch_size: Option<usize>,
+    pub(crate) id_matcher: Option<IdMatcher>,
+}
+
+/// Tells whether a response `id` answers the call sent with an `RpcId`
+type IdMatcher = fn(&RpcId, &Value) -> bool;
+
+impl Batch {
+
+    pub fn new() -> Batch {
+        Batch::default()
+    }
+
+    /// Send at most `max_batch_size` calls per HTTP request
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Batch {
+        self.max_batch_size = Some(max_batch_size);
+        self
+    }
+
+    /// Match responses to calls with `matcher` instead of id equality,
+    /// for servers that alter the ids they echo, e.g. by padding them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_v2_client::{Batch, RpcId};
+    ///
+    /// let batch = Batch::new().with_id_matcher(|sent, received| match (sent, received.as_str()) {
+    ///     (RpcId::String(sent), Some(received)) => sent.eq_ignore_ascii_case(received.trim()),
+    ///     _ => false,
+    /// });
+    /// ```
+    pub fn with_id_matcher(mut self, matcher: IdMatcher) -> Batch {
+        self.id_matcher = Some(matcher);
+        self
+    }
+
+    /// Add call of `method`, returning position of its result
+    pub fn add<P: Serialize>(&mut self, method: &str, params: Params<P>) -> Result<usize, JsonRpcError> {
+
+        // omitted params serialize as null, which the request leaves out
+        let params = serde_json::to_value(&params.0)
+            .map_err(|error| JsonRpcError::SerializationError(error.to_string()))?;
+        check_structured(&params)?;
+
+        self.calls.push((method.to_owned(), params));
+
+        Ok(self.calls.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+}
+
+/// Collect `(method, params)` pairs, ids are assigned when the batch is sent
+///
+/// # Panics
+///
+/// Panics if params fail to serialize or are not an array or object,
+/// use `Batch::add` to handle these errors instead.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_v2_client::{Batch, Params};
+///
+/// let batch: Batch = vec![("add", Params([1, 2])), ("mul", Params([3, 4]))]
+///     .into_iter()
+///     .collect();
+/// assert_eq!(batch.len(), 2);
+/// ```
+impl<M: AsRef<str>, P: Serialize> FromIterator<(M, Params<P>)> for Batch {
+
+    fn from_iter<I: IntoIterator<Item = (M, Params<P>)>>(calls: I) -> Batch {
+
+        let mut batch = Batch::new();
+        for (method, params) in calls {
+            if let Err(error) = batch.add(method.as_ref(), params) {
+                panic!("invalid params for `{}`: {}", method.as_ref(), error);
+            }
+        }
+        batch
+    }
+
+}
@@ -0,0 +1,64 @@
+use async_std::task;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{APIKey, Client, ClientConfig, JsonRpcError, Params, Request, ServiceAddress};
+
+/// Synchronous wrapper around `Client`
+///
+/// Every call blocks the current thread until the response arrives
+/// or the configured timeout expires.
+///
+/// # Examples
+///
+/// ``` no_run
+/// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+/// let client = jsonrpc_v2_client::BlockingClient::new(service_address);
+/// let product: f64 = client.call("mul", jsonrpc_v2_client::Params([2.5, 3.5]), "0").unwrap();
+/// println!("{}", product);
+/// ```
+#[derive(Debug)]
+pub struct BlockingClient {
+    inner: Client,
+}
+
+impl BlockingClient {
+
+    pub fn new(service_address: ServiceAddress) -> BlockingClient {
+        BlockingClient {
+            inner: Client::new(service_address),
+        }
+    }
+
+    /// Send `api_key` with every request
+    pub fn with_api_key(self, api_key: APIKey) -> BlockingClient {
+        BlockingClient {
+            inner: self.inner.with_api_key(api_key),
+        }
+    }
+
+    /// Send one of `api_keys` with every request, taking them round-robin
+    pub fn with_api_keys(self, api_keys: Vec<APIKey>) -> BlockingClient {
+        BlockingClient {
+            inner: self.inner.with_api_keys(api_keys),
+        }
+    }
+
+    /// Replace default client settings
+    pub fn with_config(self, config: ClientConfig) -> BlockingClient {
+        BlockingClient {
+            inner: self.inner.with_config(config),
+        }
+    }
+
+    /// Call `method` with request `id` and deserialize its result into `R`
+    pub fn call<P, R>(&self, method: &str, params: Params<P>, id: &str) -> Result<R, JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let request = Request::new(method, params, id);
+        task::block_on(self.inner.send_request(&request))
+    }
+
+}
@@ -0,0 +1,1358 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_std::future::{self, Future};
+use async_std::io::{Read as AsyncRead, Write as AsyncWrite};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::dns::Resolver;
+use crate::encoding;
+use crate::http::{self, HttpResponse};
+use crate::pool::{BufferPool, Connection, Pool};
+use crate::rate::RateLimiter;
+use crate::response::{check_id, error_array, extract_result, normalize_fields, rpc_error, unwrap_envelope};
+use crate::transport::{self, ReuseError};
+use crate::{
+    APIKey, Batch, ClientConfig, CounterIdGenerator, DynRequest, IdGenerator, JsonRpcError, Params,
+    RequestSigner, RpcId, RpcMethod, ServiceAddress, JSONRPC_VERSION,
+};
+
+/// Asynchronous JSONRPC client bound to one service
+///
+/// Request ids are generated by `IdGenerator`, by default from an increasing counter.
+/// Keep-alive connections are pooled and reused between calls.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # async_std::task::block_on(async {
+/// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+/// let client = jsonrpc_v2_client::Client::new(service_address);
+/// let product: f64 = client.call("mul", jsonrpc_v2_client::Params([2.5, 3.5])).await.unwrap();
+/// println!("{}", product);
+/// # });
+/// ```
+pub struct Client {
+    service_address: ServiceAddress,
+    /// Keys taken in turn, one per request
+    api_keys: Vec<APIKey>,
+    next_api_key: AtomicUsize,
+    config: ClientConfig,
+    id_generator: Box<dyn IdGenerator>,
+    pool: Pool,
+    buffers: BufferPool,
+    resolver: Resolver,
+    rate_limiter: RateLimiter,
+    shut_down: AtomicBool,
+    preflight_passed: AtomicBool,
+    on_connect: Option<ConnectionHook>,
+    on_disconnect: Option<ConnectionHook>,
+    error_mapper: Option<ErrorMapper>,
+    result_validator: Option<ResultValidator>,
+    on_large_response: Option<(usize, LargeResponseHook)>,
+    signer: Option<Box<dyn RequestSigner>>,
+}
+
+/// Traffic of one call, see `Client::call_with_meta`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallMeta {
+    /// Size of the HTTP request, head included
+    pub bytes_sent: usize,
+    /// Size of the HTTP response as received, before any decoding
+    pub bytes_received: usize,
+}
+
+/// Request object built by `Client` for generated ids
+#[derive(Serialize)]
+pub(crate) struct Call<'a, P: Serialize> {
+    pub jsonrpc: &'a str,
+    pub method: &'a str,
+    #[serde(skip_serializing_if = "omitted")]
+    pub params: &'a Params<P>,
+    pub id: &'a RpcId,
+}
+
+/// Request object without `id`, answered with no response object
+#[derive(Serialize)]
+struct Notification<'a, P: Serialize> {
+    jsonrpc: &'a str,
+    method: &'a str,
+    #[serde(skip_serializing_if = "omitted")]
+    params: &'a Params<P>,
+}
+
+fn omitted<P: Serialize>(params: &&Params<P>) -> bool {
+    params.is_omitted()
+}
+
+/// Response answering the call with `id` in `ClientConfig::dry_run`
+fn placeholder(id: &RpcId, result: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({"jsonrpc": JSONRPC_VERSION, "result": result, "id": id.to_value()})
+}
+
+/// Callback receiving peer address of a connection
+type ConnectionHook = Box<dyn Fn(SocketAddr) + Send + Sync>;
+
+/// Turns server specific error responses into `JsonRpcError`
+type ErrorMapper = fn(&serde_json::Value) -> Option<JsonRpcError>;
+
+/// Checks a `result` before it is deserialized, `Err` lists what is wrong with it
+type ResultValidator = Box<dyn Fn(&serde_json::Value) -> Result<(), Vec<String>> + Send + Sync>;
+
+/// Callback receiving method name and body size of a large response
+type LargeResponseHook = fn(&str, usize);
+
+impl Client {
+
+    pub fn new(service_address: ServiceAddress) -> Client {
+        Client {
+            service_address,
+            api_keys: Vec::new(),
+            next_api_key: AtomicUsize::new(0),
+            config: ClientConfig::default(),
+            id_generator: Box::new(CounterIdGenerator::default()),
+            pool: Pool::default(),
+            buffers: BufferPool::default(),
+            resolver: Resolver::default(),
+            rate_limiter: RateLimiter::default(),
+            shut_down: AtomicBool::new(false),
+            preflight_passed: AtomicBool::new(false),
+            on_connect: None,
+            on_disconnect: None,
+            error_mapper: None,
+            result_validator: None,
+            on_large_response: None,
+            signer: None,
+        }
+    }
+
+    /// Send `api_key` with every request
+    pub fn with_api_key(mut self, api_key: APIKey) -> Client {
+        self.api_keys = vec![api_key];
+        self
+    }
+
+    /// Send one of `api_keys` with every request, taking them round-robin
+    /// to spread requests over their rate limits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_v2_client::{APIKey, Client, ServiceAddress};
+    ///
+    /// let service_address = ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = Client::new(service_address)
+    ///     .with_api_keys(vec![APIKey::new("API-KEY", "first"), APIKey::new("API-KEY", "second")]);
+    /// ```
+    pub fn with_api_keys(mut self, api_keys: Vec<APIKey>) -> Client {
+        self.api_keys = api_keys;
+        self
+    }
+
+    /// Replace default client settings
+    pub fn with_config(mut self, mut config: ClientConfig) -> Client {
+
+        if config.use_env_proxy && config.proxy.is_none() {
+            config.proxy = crate::Proxy::from_env(&self.service_address.url);
+        }
+
+        self.config = config;
+        self
+    }
+
+    /// Generate request ids with `id_generator`
+    pub fn with_id_generator(mut self, id_generator: impl IdGenerator + 'static) -> Client {
+        self.id_generator = Box::new(id_generator);
+        self
+    }
+
+    /// Resolve `host:port` with `resolve` instead of system DNS
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("rpc.internal:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address)
+    ///     .with_resolver(|_| Ok(vec!["127.0.0.1:8082".parse().unwrap()]));
+    /// ```
+    pub fn with_resolver<F>(mut self, resolve: F) -> Client
+    where
+        F: Fn(&str) -> io::Result<Vec<SocketAddr>> + Send + Sync + 'static,
+    {
+        self.resolver = Resolver::with_custom(Box::new(resolve));
+        self
+    }
+
+    /// Run `hook` whenever new TCP connection is established,
+    /// reused pooled connections do not trigger it
+    pub fn on_connect(mut self, hook: impl Fn(SocketAddr) + Send + Sync + 'static) -> Client {
+        self.on_connect = Some(Box::new(hook));
+        self
+    }
+
+    /// Run `hook` whenever connection is closed instead of returned to the pool
+    pub fn on_disconnect(mut self, hook: impl Fn(SocketAddr) + Send + Sync + 'static) -> Client {
+        self.on_disconnect = Some(Box::new(hook));
+        self
+    }
+
+    /// Run `hook` with the method name and body size of every response
+    /// body larger than `soft_limit` bytes, e.g. to monitor memory use
+    ///
+    /// Batches report the methods of their calls joined by `,`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_v2_client::{Client, ServiceAddress};
+    ///
+    /// fn warn(method: &str, bytes: usize) {
+    ///     eprintln!("{} answered with {} bytes", method, bytes);
+    /// }
+    ///
+    /// let client = Client::new(ServiceAddress::new("127.0.0.1:8082", "/api")).on_large_response(1 << 20, warn);
+    /// ```
+    pub fn on_large_response(mut self, soft_limit: usize, hook: LargeResponseHook) -> Client {
+        self.on_large_response = Some((soft_limit, hook));
+        self
+    }
+
+    /// Inspect every response object with `mapper` before the default
+    /// result / error extraction, `Some` error is returned as is
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_v2_client::{Client, JsonRpcError, ServiceAddress};
+    ///
+    /// fn legacy_errors(response: &serde_json::Value) -> Option<JsonRpcError> {
+    ///     let message = response.get("err")?.as_str()?;
+    ///     Some(JsonRpcError::Rpc {
+    ///         code: -32000,
+    ///         message: message.to_owned(),
+    ///         data: None,
+    ///         id: None,
+    ///     })
+    /// }
+    ///
+    /// let client = Client::new(ServiceAddress::new("127.0.0.1:8082", "/api")).with_error_mapper(legacy_errors);
+    /// ```
+    pub fn with_error_mapper(mut self, mapper: ErrorMapper) -> Client {
+        self.error_mapper = Some(mapper);
+        self
+    }
+
+    /// Check every `result` with `validator` before deserializing it,
+    /// e.g. against a JSON schema, failing the call with `ResponseError`
+    /// carrying the request id
+    /// carrying the validation messages
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonrpc_v2_client::{Client, ServiceAddress};
+    ///
+    /// let client = Client::new(ServiceAddress::new("127.0.0.1:8082", "/api")).with_result_validator(|result| {
+    ///     if result.is_i64() {
+    ///         Ok(())
+    ///     } else {
+    ///         Err(vec![format!("{} is not an integer", result)])
+    ///     }
+    /// });
+    /// ```
+    pub fn with_result_validator<F>(mut self, validator: F) -> Client
+    where
+        F: Fn(&serde_json::Value) -> Result<(), Vec<String>> + Send + Sync + 'static,
+    {
+        self.result_validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Add headers computed by `signer` over the body to every request
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "hmac")] {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let signer = jsonrpc_v2_client::HmacSha256Signer::new(b"secret");
+    /// let client = jsonrpc_v2_client::Client::new(service_address).with_signer(signer);
+    /// # }
+    /// ```
+    pub fn with_signer(mut self, signer: impl RequestSigner + 'static) -> Client {
+        self.signer = Some(Box::new(signer));
+        self
+    }
+
+    /// Call `method` and deserialize its result into `R`
+    pub async fn call<P, R>(&self, method: &str, params: Params<P>) -> Result<R, JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let call = Call {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params: &params,
+            id: &id,
+        };
+
+        self.send_payload(&call, method, &id).await
+    }
+
+    /// Call `method` and return its result together with `CallMeta`
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let (product, meta): (f64, _) = client.call_with_meta("mul", jsonrpc_v2_client::Params([2.5, 3.5])).await.unwrap();
+    /// println!("{} for {} bytes sent, {} received", product, meta.bytes_sent, meta.bytes_received);
+    /// # });
+    /// ```
+    pub async fn call_with_meta<P, R>(&self, method: &str, params: Params<P>) -> Result<(R, CallMeta), JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let call = Call {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params: &params,
+            id: &id,
+        };
+        let encoded = self.encode(&call, &[])?;
+
+        self.send_encoded_with_meta(&encoded, method, &id, self.config.timeout).await
+    }
+
+    /// Call `method` allowing it `timeout` instead of `ClientConfig::timeout`
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// use std::time::Duration;
+    ///
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let report: String = client
+    ///     .call_with_timeout("report", jsonrpc_v2_client::Params::omitted(), Duration::from_secs(300))
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    pub async fn call_with_timeout<P, R>(
+        &self,
+        method: &str,
+        params: Params<P>,
+        timeout: Duration,
+    ) -> Result<R, JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let call = Call {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params: &params,
+            id: &id,
+        };
+        let encoded = self.encode(&call, &[])?;
+
+        self.send_encoded(&encoded, method, &id, Some(timeout)).await
+    }
+
+    /// Call `method` sending additional HTTP `headers`
+    ///
+    /// Headers override `ClientConfig::default_headers` of the same name.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let product: f64 = client
+    ///     .call_with_headers("mul", jsonrpc_v2_client::Params([2.5, 3.5]), &[("X-Request-ID", "abc")])
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    pub async fn call_with_headers<P, R>(
+        &self,
+        method: &str,
+        params: Params<P>,
+        headers: &[(&str, &str)],
+    ) -> Result<R, JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let call = Call {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params: &params,
+            id: &id,
+        };
+
+        self.send_payload_with_headers(&call, method, &id, headers).await
+    }
+
+    /// Call `method` with params JSON produced by `write_params`
+    ///
+    /// Params are written straight into the request body, so large
+    /// or lazily generated params are never built as `serde_json::Value`.
+    /// The body is still buffered in full before sending, it sets
+    /// `Content-Length` and is what compression and signing work on.
+    /// The writer must produce valid JSON array or object.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// use std::io::Write;
+    ///
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let sum: f64 = client
+    ///     .call_streaming("sum", |writer| {
+    ///         writer.write_all(b"[")?;
+    ///         for i in 0..1000 {
+    ///             let separator = if i == 0 { "" } else { "," };
+    ///             write!(writer, "{}{}", separator, i)?;
+    ///         }
+    ///         writer.write_all(b"]")
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    pub async fn call_streaming<W, R>(&self, method: &str, write_params: W) -> Result<R, JsonRpcError>
+    where
+        W: FnOnce(&mut dyn Write) -> io::Result<()>,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let serialization_error = |error: io::Error| JsonRpcError::SerializationError(error.to_string());
+
+        // body is buffered so Content-Length is known before anything is sent
+        let mut body = Vec::new();
+        write!(
+            body,
+            "{{\"jsonrpc\":\"{}\",\"method\":{},\"params\":",
+            JSONRPC_VERSION,
+            serde_json::Value::from(method)
+        )
+        .map_err(serialization_error)?;
+        write_params(&mut body).map_err(serialization_error)?;
+        write!(body, ",\"id\":{}}}", id.to_value()).map_err(serialization_error)?;
+
+        let encoded = self.frame(&body, &[])?;
+
+        self.send_encoded(&encoded, method, &id, self.config.timeout).await
+    }
+
+    /// Call method described by `M`
+    ///
+    /// Serialization errors name the method and its `Output` type.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// struct Mul;
+    ///
+    /// impl jsonrpc_v2_client::RpcMethod for Mul {
+    ///     const NAME: &'static str = "mul";
+    ///     type Params = [f64; 2];
+    ///     type Output = f64;
+    /// }
+    ///
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let product = client.invoke::<Mul>([2.5, 3.5]).await.unwrap();
+    /// # });
+    /// ```
+    pub async fn invoke<M: RpcMethod>(&self, params: M::Params) -> Result<M::Output, JsonRpcError> {
+        self.call(M::NAME, Params(params)).await.map_err(|error| match error {
+            JsonRpcError::SerializationError(message) => JsonRpcError::SerializationError(format!(
+                "method `{}` with output `{}`: {}",
+                M::NAME,
+                std::any::type_name::<M::Output>(),
+                message
+            )),
+            error => error,
+        })
+    }
+
+    /// Send notification of method `M`, returning once it is written
+    ///
+    /// The server answers notifications with no response object, so the HTTP
+    /// response is not read and the connection is closed instead of pooled.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// struct Log;
+    ///
+    /// impl jsonrpc_v2_client::RpcMethod for Log {
+    ///     const NAME: &'static str = "log";
+    ///     type Params = [String; 1];
+    ///     type Output = ();
+    /// }
+    ///
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// client.notify::<Log>(["started".to_owned()]).await.unwrap();
+    /// # });
+    /// ```
+    pub async fn notify<M: RpcMethod>(&self, params: M::Params) -> Result<(), JsonRpcError> {
+
+        let params = Params(params);
+        let notification = Notification {
+            jsonrpc: JSONRPC_VERSION,
+            method: M::NAME,
+            params: &params,
+        };
+        let encoded = self.encode(&notification, &[])?;
+
+        if self.config.dry_run {
+            return Ok(());
+        }
+
+        self.limited(async {
+            // dropping the guard closes the connection
+            let mut guard = ConnectionGuard::new(self, self.connect().await?);
+            transport::write_buffered(guard.stream(), &encoded, self.config.write_buffer_size)
+                .await
+                .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))
+        })
+        .await
+    }
+
+    /// Call `method` over `stream` opened by the caller instead of a pooled connection
+    ///
+    /// The HTTP exchange is written to and read from `stream` as is, so it can be
+    /// any transport the client does not support itself, or an in-memory pipe.
+    /// Preflight and connection hooks are skipped, the stream is left open.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let mut stream = async_std::net::TcpStream::connect("127.0.0.1:8082").await.unwrap();
+    /// let product: f64 = client
+    ///     .call_over(&mut stream, "mul", jsonrpc_v2_client::Params([2.5, 3.5]))
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    pub async fn call_over<S, P, R>(&self, stream: &mut S, method: &str, params: Params<P>) -> Result<R, JsonRpcError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id();
+        let call = Call {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params: &params,
+            id: &id,
+        };
+        let encoded = self.encode(&call, &[])?;
+
+        if self.config.dry_run {
+            return extract_result(placeholder(&id, &self.config.dry_run_result), &id);
+        }
+
+        let response = self
+            .guarded(self.config.timeout, Some(&id), async {
+                let response = transport::roundtrip(stream, &encoded, &self.config, Vec::new()).await?;
+                transport::parse_body(&response)
+            })
+            .await?;
+
+        self.settle(response, &id)
+    }
+
+    /// Check the endpoint is alive by sending it an `OPTIONS` request,
+    /// returning how long the server took to answer
+    ///
+    /// Any HTTP response counts as alive, whatever its status.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// match client.health_check().await {
+    ///     Ok(latency) => println!("alive, answered in {:?}", latency),
+    ///     Err(error) => println!("unavailable: {}", error),
+    /// }
+    /// # });
+    /// ```
+    pub async fn health_check(&self) -> Result<Duration, JsonRpcError> {
+
+        if self.config.dry_run {
+            return Ok(Duration::ZERO);
+        }
+
+        let request = http::encode_options(&self.service_address, &self.config);
+        let start = Instant::now();
+
+        let response = self.limited(self.roundtrip(&request)).await?;
+        let latency = start.elapsed();
+        self.buffers.checkin(response.body, self.config.max_buffer_size);
+
+        Ok(latency)
+    }
+
+    /// Fetch the OpenRPC document describing the service by calling `rpc.discover`
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// let document = client.discover().await.unwrap();
+    /// for method in document["methods"].as_array().into_iter().flatten() {
+    ///     println!("{}", method["name"]);
+    /// }
+    /// # });
+    /// ```
+    pub async fn discover(&self) -> Result<serde_json::Value, JsonRpcError> {
+        self.call("rpc.discover", Params::omitted()).await
+    }
+
+    /// Close pooled connections and reject all further calls
+    /// with `JsonRpcError::ClientShutdown`
+    ///
+    /// Takes `&self`, so a client shared between tasks can be shut down
+    /// while other holders still reference it.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// # async_std::task::block_on(async {
+    /// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+    /// let client = jsonrpc_v2_client::Client::new(service_address);
+    /// client.shutdown().await;
+    /// # });
+    /// ```
+    pub async fn shutdown(&self) {
+
+        self.shut_down.store(true, Ordering::SeqCst);
+
+        for connection in self.pool.drain() {
+            if let Err(error) = connection.stream.shutdown(Shutdown::Both) {
+                log::debug!(
+                    target: "jsonrpc_v2_client",
+                    "[jsonrpc_v2_client: closing connection failed]: {}",
+                    error
+                );
+            }
+            self.disconnected(connection);
+        }
+
+        log::info!(
+            target: "jsonrpc_v2_client",
+            "[jsonrpc_v2_client: client shut down]"
+        );
+    }
+
+    /// Send all calls of `batch`, returning their results in the order they were added
+    ///
+    /// Responses are matched to calls by id, or by `Batch::with_id_matcher`,
+    /// calls the server did not answer get `ResponseError`. An error
+    /// rejecting a whole (sub-)batch fails the send.
+    /// Ids generated for the calls must be unique, otherwise nothing is sent.
+    pub async fn send_batch(
+        &self,
+        batch: Batch,
+    ) -> Result<Vec<Result<serde_json::Value, JsonRpcError>>, JsonRpcError> {
+
+        let id_matcher = batch.id_matcher;
+        let requests: Vec<DynRequest> = batch
+            .calls
+            .into_iter()
+            .map(|(method, params)| DynRequest::new(&method, params, self.next_id()))
+            .collect();
+        let positions: HashMap<&RpcId, usize> = requests
+            .iter()
+            .enumerate()
+            .map(|(position, request)| (&request.id, position))
+            .collect();
+
+        // responses could not be told apart, e.g. with a constant IdGenerator
+        if positions.len() != requests.len() {
+            return Err(JsonRpcError::SerializationError("duplicate id in batch".to_owned()));
+        }
+        let mut results = vec![None; requests.len()];
+        let size = batch.max_batch_size.unwrap_or(requests.len()).max(1);
+
+        for chunk in requests.chunks(size) {
+
+            let encoded = self.encode(chunk, &[])?;
+            let methods: Vec<&str> = chunk.iter().map(|request| request.method.as_str()).collect();
+
+            if self.config.dry_run {
+                for request in chunk {
+                    results[positions[&request.id]] = Some(extract_result(placeholder(&request.id, &self.config.dry_run_result), &request.id));
+                }
+                continue;
+            }
+
+            let responses = match self.send_framed(&encoded, &methods.join(","), None, self.config.timeout).await?.0 {
+                serde_json::Value::Array(responses) => responses,
+                response => {
+                    return Err(match response.get("error") {
+                        Some(error) => rpc_error(error.clone(), None),
+                        None => JsonRpcError::response_error("batch response is not an array".to_owned()),
+                    })
+                }
+            };
+
+            for response in responses {
+                let position = match (id_matcher, response.get("id")) {
+                    (Some(matches), Some(id)) => chunk
+                        .iter()
+                        .find(|request| matches(&request.id, id))
+                        .map(|request| positions[&request.id]),
+                    _ => response
+                        .get("id")
+                        .and_then(|id| serde_json::from_value::<RpcId>(id.clone()).ok())
+                        .and_then(|id| positions.get(&id).copied()),
+                };
+                if let Some(position) = position {
+                    results[position] = Some(match self.mapped_error(&response) {
+                        Some(error) => Err(error),
+                        None => self
+                            .validate(&response)
+                            .map_err(|error| error.for_request(&requests[position].id))
+                            .and_then(|_| extract_result(response, &requests[position].id)),
+                    });
+                }
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .zip(&requests)
+            .map(|(result, request)| {
+                result.unwrap_or_else(|| {
+                    Err(JsonRpcError::response_error("no response for request").for_request(&request.id))
+                })
+            })
+            .collect())
+    }
+
+    /// Send prepared `request` honoring client settings
+    #[cfg(feature = "blocking")]
+    pub(crate) async fn send_request<P, R>(&self, request: &crate::Request<P>) -> Result<R, JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.send_payload(request, &request.method, &RpcId::from(request.id()))
+            .await
+    }
+
+    /// Serialize and send request object `payload` calling `method` with `id`
+    async fn send_payload<B, R>(&self, payload: &B, method: &str, id: &RpcId) -> Result<R, JsonRpcError>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        self.send_payload_with_headers(payload, method, id, &[]).await
+    }
+
+    /// Serialize and send request object `payload` calling `method` with `id` and extra HTTP `headers`
+    async fn send_payload_with_headers<B, R>(
+        &self,
+        payload: &B,
+        method: &str,
+        id: &RpcId,
+        headers: &[(&str, &str)],
+    ) -> Result<R, JsonRpcError>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        let encoded = self.encode(payload, headers)?;
+
+        self.send_encoded(&encoded, method, id, self.config.timeout).await
+    }
+
+    /// Serialize `payload` and frame it as HTTP request, see `frame`
+    fn encode<B: Serialize + ?Sized>(&self, payload: &B, headers: &[(&str, &str)]) -> Result<Vec<u8>, JsonRpcError> {
+
+        let body = serde_json::to_vec_pretty(payload)
+            .map_err(|error| JsonRpcError::SerializationError(error.to_string()))?;
+
+        self.frame(&body, headers)
+    }
+
+    /// Frame `body` as HTTP request with extra `headers` and those of the signer,
+    /// rejecting bodies over `ClientConfig::max_request_size`
+    ///
+    /// The body is compressed before it is measured and signed, so both
+    /// apply to the bytes the server receives.
+    fn frame(&self, body: &[u8], headers: &[(&str, &str)]) -> Result<Vec<u8>, JsonRpcError> {
+
+        let compressed = encoding::compress_request(body, &self.config);
+        let body = compressed.as_deref().unwrap_or(body);
+
+        if self.config.max_request_size.is_some_and(|max_size| body.len() > max_size) {
+            return Err(JsonRpcError::SerializationError("request too large".to_owned()));
+        }
+
+        let signed = match &self.signer {
+            Some(signer) => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs());
+                signer.sign(body, timestamp)
+            }
+            None => Vec::new(),
+        };
+
+        let mut headers = headers.to_vec();
+        if compressed.is_some() {
+            headers.push(("Content-Encoding", "gzip"));
+        }
+        headers.extend(signed.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+
+        Ok(transport::frame(body, &self.service_address, self.next_api_key(), &self.config, &headers))
+    }
+
+    /// Key for the next request, `None` without keys
+    fn next_api_key(&self) -> Option<&APIKey> {
+
+        if self.api_keys.is_empty() {
+            return None;
+        }
+
+        let turn = self.next_api_key.fetch_add(1, Ordering::Relaxed);
+        self.api_keys.get(turn % self.api_keys.len())
+    }
+
+    /// Next id of `id_generator`, string ids namespaced by `ClientConfig::id_prefix`
+    fn next_id(&self) -> RpcId {
+
+        match (self.id_generator.next_id(), &self.config.id_prefix) {
+            (RpcId::String(id), Some(prefix)) => RpcId::String(format!("{}-{}", prefix, id)),
+            (id, _) => id,
+        }
+    }
+
+    /// Send framed request with `id` within `timeout` and extract its result
+    async fn send_encoded<R: DeserializeOwned>(
+        &self,
+        encoded: &[u8],
+        method: &str,
+        id: &RpcId,
+        timeout: Option<Duration>,
+    ) -> Result<R, JsonRpcError> {
+
+        self.send_encoded_with_meta(encoded, method, id, timeout)
+            .await
+            .map(|(result, _)| result)
+    }
+
+    /// Like `send_encoded`, also returning the traffic of the call
+    async fn send_encoded_with_meta<R: DeserializeOwned>(
+        &self,
+        encoded: &[u8],
+        method: &str,
+        id: &RpcId,
+        timeout: Option<Duration>,
+    ) -> Result<(R, CallMeta), JsonRpcError> {
+
+        log::trace!(
+            target: "jsonrpc_v2_client",
+            "[jsonrpc_v2_client: sending request id = {}]",
+            id.to_value()
+        );
+
+        if self.config.dry_run {
+            log::debug!(
+                target: "jsonrpc_v2_client",
+                "[jsonrpc_v2_client: dry run, not sending request id = {}]",
+                id.to_value()
+            );
+            return extract_result(placeholder(id, &self.config.dry_run_result), id).map(|result| (result, CallMeta::default()));
+        }
+
+        let (response, meta) = self.send_framed(encoded, method, Some(id), timeout).await?;
+
+        self.settle(response, id).map(|result| (result, meta))
+    }
+
+    /// Turn `response` object into the result of the call with `id`
+    fn settle<R: DeserializeOwned>(&self, response: serde_json::Value, id: &RpcId) -> Result<R, JsonRpcError> {
+
+        log::trace!(
+            target: "jsonrpc_v2_client",
+            "[jsonrpc_v2_client: received response id = {}]",
+            response.get("id").unwrap_or(&serde_json::Value::Null)
+        );
+
+        if let Some(error) = self.mapped_error(&response) {
+            return Err(error);
+        }
+
+        check_id(&response, id)?;
+        self.validate(&response).map_err(|error| error.for_request(id))?;
+        extract_result(response, id)
+    }
+
+    /// Run `result_validator` on the `result` of `response`, if both exist
+    fn validate(&self, response: &serde_json::Value) -> Result<(), JsonRpcError> {
+
+        match (&self.result_validator, response.get("result")) {
+            (Some(validator), Some(result)) => validator(result).map_err(|messages| {
+                JsonRpcError::response_error(format!("result failed validation: {}", messages.join("; ")))
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn mapped_error(&self, response: &serde_json::Value) -> Option<JsonRpcError> {
+        self.error_mapper.and_then(|mapper| mapper(response))
+    }
+
+    /// Send framed request and return response object with the traffic, honoring `timeout`
+    ///
+    /// `id` is that of a single call, `None` for batches.
+    async fn send_framed(
+        &self,
+        encoded: &[u8],
+        method: &str,
+        id: Option<&RpcId>,
+        timeout: Option<Duration>,
+    ) -> Result<(serde_json::Value, CallMeta), JsonRpcError> {
+
+        let mut bytes_received = 0;
+        let response = self
+            .guarded(timeout, id, async {
+                self.preflight().await?;
+                let (response, size) = self.exchange(encoded, method).await?;
+                bytes_received = size;
+                Ok(response)
+            })
+            .await?;
+
+        let meta = CallMeta {
+            bytes_sent: encoded.len(),
+            bytes_received,
+        };
+
+        Ok((response, meta))
+    }
+
+    /// Run `exchange` unless shut down, honoring `timeout`, `unwrap_path`,
+    /// `lenient_fields` and `error_array_fallback`, errors of the fallback carry `id`
+    async fn guarded<F>(
+        &self,
+        timeout: Option<Duration>,
+        id: Option<&RpcId>,
+        exchange: F,
+    ) -> Result<serde_json::Value, JsonRpcError>
+    where
+        F: Future<Output = Result<serde_json::Value, JsonRpcError>>,
+    {
+        let response = self.within(timeout, exchange).await?;
+
+        let mut response = match &self.config.unwrap_path {
+            Some(path) => unwrap_envelope(response, path).map_err(|error| match id {
+                Some(id) => error.for_request(id),
+                None => error,
+            })?,
+            None => response,
+        };
+
+        if self.config.lenient_fields {
+            normalize_fields(&mut response);
+        }
+
+        if self.config.error_array_fallback {
+            if let Some(error) = error_array(&response, id) {
+                return Err(error);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Run `operation` unless shut down, honoring timeout
+    async fn limited<T, F>(&self, operation: F) -> Result<T, JsonRpcError>
+    where
+        F: Future<Output = Result<T, JsonRpcError>>,
+    {
+        self.within(self.config.timeout, operation).await
+    }
+
+    /// Run `operation` unless shut down, failing once `timeout` elapsed
+    ///
+    /// Under `ClientConfig::rate_limit` the turn of `operation` is awaited first,
+    /// the timeout starts once it is taken.
+    async fn within<T, F>(&self, timeout: Option<Duration>, operation: F) -> Result<T, JsonRpcError>
+    where
+        F: Future<Output = Result<T, JsonRpcError>>,
+    {
+        if self.shut_down.load(Ordering::SeqCst) {
+            return Err(JsonRpcError::ClientShutdown);
+        }
+
+        if let Some((calls, window)) = self.config.rate_limit {
+            self.rate_limiter.acquire(calls, window).await;
+        }
+
+        match timeout {
+            Some(timeout) => future::timeout(timeout, operation)
+                .await
+                .map_err(|_| JsonRpcError::Timeout)?,
+            None => operation.await,
+        }
+    }
+
+    /// Check once per client that the endpoint allows our POST requests,
+    /// when `ClientConfig::preflight` is set
+    async fn preflight(&self) -> Result<(), JsonRpcError> {
+
+        if !self.config.preflight || self.preflight_passed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let mut headers = vec!["content-type"];
+        // rotated keys are expected to share a header name
+        if let Some(api_key) = self.api_keys.first() {
+            headers.push(&api_key.0);
+        }
+        if self.service_address.authorization.is_some() {
+            headers.push("authorization");
+        }
+        headers.extend(self.config.default_headers.iter().map(|(name, _)| name.as_str()));
+
+        let request = http::encode_preflight(&self.service_address, &self.config, &headers.join(", "));
+        let response = self.roundtrip(&request).await?;
+
+        if !(200..300).contains(&response.status) {
+            return Err(JsonRpcError::Preflight(format!("HTTP status {}", response.status)));
+        }
+
+        let allows = |header: &str, item: &str| {
+            response.header(header).is_some_and(|values| {
+                values
+                    .split(',')
+                    .map(str::trim)
+                    .any(|value| value == "*" || value.eq_ignore_ascii_case(item))
+            })
+        };
+
+        if !allows("Access-Control-Allow-Methods", "POST") {
+            return Err(JsonRpcError::Preflight("POST not allowed".to_owned()));
+        }
+
+        if let Some(header) = headers.iter().find(|header| !allows("Access-Control-Allow-Headers", header)) {
+            return Err(JsonRpcError::Preflight(format!("header {} not allowed", header)));
+        }
+
+        self.preflight_passed.store(true, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Send framed `request` calling `method` and parse the response body,
+    /// returned with the size of the response
+    async fn exchange(&self, request: &[u8], method: &str) -> Result<(serde_json::Value, usize), JsonRpcError> {
+
+        let response = self.roundtrip(request).await?;
+
+        if let Some((soft_limit, hook)) = self.on_large_response {
+            if response.body.len() > soft_limit {
+                hook(method, response.body.len());
+            }
+        }
+
+        let size = response.size;
+        let body = transport::parse_body(&response);
+
+        self.buffers.checkin(response.body, self.config.max_buffer_size);
+
+        body.map(|body| (body, size))
+    }
+
+    /// Send framed `request` over pooled or new connection
+    ///
+    /// A pooled connection found closed before anything was answered is
+    /// replaced by a new one, since the server may have closed it while idle.
+    /// Other failures may leave the request received and are returned, so
+    /// it is never sent twice.
+    async fn roundtrip(&self, request: &[u8]) -> Result<HttpResponse, JsonRpcError> {
+
+        if let Some(connection) = self.pool.checkout() {
+            let mut guard = ConnectionGuard::new(self, connection);
+            match transport::roundtrip_reused(guard.stream(), request, &self.config, self.buffers.checkout()).await {
+                Ok(response) => return Ok(guard.release(response)),
+                Err(ReuseError::Failed(error)) => return Err(error),
+                Err(ReuseError::Stale(error)) => {
+                    log::debug!(
+                        target: "jsonrpc_v2_client",
+                        "[jsonrpc_v2_client: pooled connection failed, reconnecting]: {}",
+                        error
+                    );
+                }
+            }
+        }
+
+        let mut guard = ConnectionGuard::new(self, self.connect().await?);
+        let response = transport::roundtrip(guard.stream(), request, &self.config, self.buffers.checkout()).await?;
+
+        Ok(guard.release(response))
+    }
+
+    async fn connect(&self) -> Result<Connection, JsonRpcError> {
+
+        transport::check_scheme(&self.service_address)?;
+
+        if let Some(proxy) = &self.config.proxy {
+            return self.connect_proxy(proxy).await;
+        }
+
+        let addresses = self.resolve(&self.service_address.url).await?;
+        let stream = transport::connect_any(&addresses).await?;
+
+        self.connected(stream)
+    }
+
+    /// Connect to the service through `proxy`, peer is the proxy address
+    async fn connect_proxy(&self, proxy: &crate::Proxy) -> Result<Connection, JsonRpcError> {
+
+        match proxy {
+            crate::Proxy::Http { addr } => {
+                let addresses = self.resolve(addr).await?;
+                let stream = transport::connect_any(&addresses).await?;
+
+                self.connected(stream)
+            }
+            #[cfg(feature = "socks")]
+            crate::Proxy::Socks5 { addr, auth } => {
+                let addresses = self.resolve(addr).await?;
+                let mut stream = transport::connect_any(&addresses).await?;
+                crate::socks::socks5_connect(&mut stream, &self.service_address.url, auth.as_ref()).await?;
+
+                self.connected(stream)
+            }
+        }
+    }
+
+    /// Addresses of `authority` allowed by `ClientConfig::ip_version`
+    async fn resolve(&self, authority: &str) -> Result<Vec<SocketAddr>, JsonRpcError> {
+
+        let mut addresses = self.resolver.resolve(authority, self.config.dns_cache_ttl).await?;
+        addresses.retain(|address| self.config.ip_version.allows(address));
+
+        if addresses.is_empty() {
+            return Err(JsonRpcError::ResolutionError(format!(
+                "{}: no addresses allowed by {:?}",
+                authority, self.config.ip_version
+            )));
+        }
+
+        Ok(addresses)
+    }
+
+    fn connected(&self, stream: async_std::net::TcpStream) -> Result<Connection, JsonRpcError> {
+
+        transport::configure(&stream, &self.config)?;
+
+        let peer = stream
+            .peer_addr()
+            .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?;
+
+        if let Some(hook) = &self.on_connect {
+            hook(peer);
+        }
+
+        Ok(Connection { stream, peer })
+    }
+
+    /// Return `connection` to the pool when `response` allows it
+    fn release(&self, connection: Connection, response: HttpResponse) -> HttpResponse {
+
+        if response.keep_alive && !self.shut_down.load(Ordering::SeqCst) {
+            if let Err(connection) = self.pool.checkin(connection, self.config.max_idle_connections) {
+                self.disconnected(connection);
+            }
+        } else {
+            self.disconnected(connection);
+        }
+
+        response
+    }
+
+    fn disconnected(&self, connection: Connection) {
+
+        if let Some(hook) = &self.on_disconnect {
+            hook(connection.peer);
+        }
+    }
+
+}
+
+/// Connection in use by a single exchange
+///
+/// Only `release` may return the connection to the pool. Dropping the guard,
+/// e.g. because the exchange failed or its future was cancelled halfway,
+/// closes the connection, which may hold a partial request or response.
+struct ConnectionGuard<'a> {
+    client: &'a Client,
+    connection: Option<Connection>,
+}
+
+impl<'a> ConnectionGuard<'a> {
+
+    fn new(client: &'a Client, connection: Connection) -> ConnectionGuard<'a> {
+        ConnectionGuard {
+            client,
+            connection: Some(connection),
+        }
+    }
+
+    fn stream(&mut self) -> &mut async_std::net::TcpStream {
+        &mut self.connection.as_mut().expect("connection taken before drop").stream
+    }
+
+    /// Exchange completed with `response`, pool the connection if it allows
+    fn release(mut self, response: HttpResponse) -> HttpResponse {
+        let connection = self.connection.take().expect("connection taken before drop");
+        self.client.release(connection, response)
+    }
+
+}
+
+impl Drop for ConnectionGuard<'_> {
+
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.client.disconnected(connection);
+        }
+    }
+
+}
+
+impl fmt::Debug for Client {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("service_address", &self.service_address)
+            .field("api_keys", &self.api_keys)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use crate::{Client, Params, ServiceAddress};
+
+    /// Keep-alive server answering `calls` requests on a single connection,
+    /// requests without body, e.g. `OPTIONS`, get `id: null`
+    fn serve(calls: usize) -> ServiceAddress {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            for _ in 0..calls {
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let request: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+
+                let body = serde_json::json!({"jsonrpc": "2.0", "result": 42, "id": request["id"]}).to_string();
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+                writer.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        ServiceAddress::new(&url, "/api")
+    }
+
+    #[test]
+    fn test_sequential_calls_reuse_buffer() {
+        let client = Client::new(serve(50));
+
+        async_std::task::block_on(async {
+            // ids grow to two digits, so the buffer may grow until then
+            for _ in 0..10 {
+                let _: u32 = client.call("answer", Params([1])).await.unwrap();
+            }
+            let idle = client.buffers.idle();
+            assert_eq!(idle.len(), 1);
+
+            for _ in 0..40 {
+                let result: u32 = client.call("answer", Params([1])).await.unwrap();
+                assert_eq!(result, 42);
+                assert_eq!(client.buffers.idle(), idle);
+            }
+        });
+    }
+
+    #[test]
+    fn test_health_check_returns_buffer() {
+        let client = Client::new(serve(3));
+
+        async_std::task::block_on(async {
+            let _: u32 = client.call("answer", Params([1])).await.unwrap();
+            assert_eq!(client.buffers.idle().len(), 1);
+
+            // the buffer may grow for the response, but goes back to the pool
+            client.health_check().await.unwrap();
+            client.health_check().await.unwrap();
+            assert_eq!(client.buffers.idle().len(), 1);
+        });
+    }
+
+}
@@ -0,0 +1,316 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::JsonRpcError;
+
+/// Client settings
+///
+/// # Examples
+///
+/// ```
+/// let config = jsonrpc_v2_client::ClientConfig {
+///     timeout: Some(std::time::Duration::from_secs(5)),
+///     ..Default::default()
+/// };
+/// println!("{:?}", config);
+/// ```
+///
+/// Settings can be loaded from configuration files, durations are given
+/// in seconds or as strings understood by `parse_duration`,
+/// missing fields take their default values:
+///
+/// ```
+/// let config: jsonrpc_v2_client::ClientConfig =
+///     serde_json::from_str(r#"{"timeout": 2.5, "read_idle_timeout": "500ms", "dns_cache_ttl": null}"#).unwrap();
+/// assert_eq!(config.timeout, Some(std::time::Duration::from_millis(2500)));
+/// assert_eq!(config.read_idle_timeout, Some(std::time::Duration::from_millis(500)));
+/// assert_eq!(config.dns_cache_ttl, None);
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// Limit for the whole request / response exchange, `None` waits forever
+    #[serde(with = "seconds")]
+    pub timeout: Option<Duration>,
+    /// Limit for each read of the response, guarding against servers
+    /// trickling bytes, `None` waits as long as `timeout` allows
+    #[serde(with = "seconds")]
+    pub read_idle_timeout: Option<Duration>,
+    /// Idle keep-alive connections kept for reuse, 0 disables pooling
+    pub max_idle_connections: usize,
+    /// Disable Nagle's algorithm on new connections
+    pub tcp_nodelay: bool,
+    /// Send TCP keepalive probes after connection was idle this long,
+    /// `None` leaves the system default
+    #[serde(with = "seconds")]
+    pub tcp_keepalive: Option<Duration>,
+    /// `Accept` header value, `None` omits the header
+    pub accept_header: Option<String>,
+    /// How long resolved addresses are reused, `None` resolves on every connect
+    #[serde(with = "seconds")]
+    pub dns_cache_ttl: Option<Duration>,
+    /// Dotted path to the JSONRPC response inside a gateway envelope,
+    /// e.g. `data` for `{"data": {...}, "status": "ok"}`
+    pub unwrap_path: Option<String>,
+    /// Accept response members `jsonrpc`, `result`, `error` and `id`
+    /// in any letter case, e.g. `jsonRpc` or `Result`, from non-compliant servers
+    pub lenient_fields: bool,
+    /// Size of the buffer requests are written through, flushed once per request
+    pub write_buffer_size: usize,
+    /// Headers sent with every request, e.g. `traceparent`,
+    /// per-request headers of the same name take precedence
+    pub default_headers: Vec<(String, String)>,
+    /// `Host` header value, `None` uses the service address `url`
+    pub host_override: Option<String>,
+    /// Send `OPTIONS` preflight before the first call and fail unless
+    /// the endpoint allows POST with the headers the client sends
+    pub preflight: bool,
+    /// Largest response buffer kept for reuse between calls, 0 disables reuse
+    pub max_buffer_size: usize,
+    /// Largest request body in bytes as sent, after `compress_request`,
+    /// larger ones fail before connecting, `None` sends any size
+    pub max_request_size: Option<usize>,
+    /// Proxy to connect through, `None` connects directly
+    pub proxy: Option<crate::Proxy>,
+    /// Without `proxy`, take the proxy from `HTTP_PROXY` unless the service
+    /// host is listed in `NO_PROXY`, read when the config is given to `Client`
+    pub use_env_proxy: bool,
+    /// Namespace prepended to generated string ids, `Some("clientA")`
+    /// turns `"1"` into `"clientA-1"`, against collisions between clients
+    pub id_prefix: Option<String>,
+    /// Answer every call with `dry_run_result` instead of sending it,
+    /// for exercising call sites without a server
+    pub dry_run: bool,
+    /// Result of calls in `dry_run`, the default `null` only deserializes
+    /// into outputs like `Option<_>`, `()` or `serde_json::Value`,
+    /// typed outputs such as `f64` need a matching value here
+    pub dry_run_result: serde_json::Value,
+    /// Gzip request bodies of at least `compress_request_threshold` bytes,
+    /// needs the `gzip` feature and a server accepting `Content-Encoding: gzip`
+    pub compress_request: bool,
+    /// Smallest body compressed with `compress_request`, below it
+    /// the gzip overhead outweighs the savings
+    pub compress_request_threshold: usize,
+    /// IP versions of resolved addresses connected to, e.g. `V4Only`
+    /// where the IPv6 route is broken
+    pub ip_version: IpPreference,
+    /// At most this many calls per window, further calls wait for their turn,
+    /// e.g. `Some((10, Duration::from_secs(1)))`
+    #[serde(with = "rate")]
+    pub rate_limit: Option<(u32, Duration)>,
+    /// Read responses shaped `["error", code, message]`, sent by some
+    /// non-compliant servers, as `JsonRpcError::Rpc`
+    pub error_array_fallback: bool,
+}
+
+impl Default for ClientConfig {
+
+    fn default() -> ClientConfig {
+        ClientConfig {
+            timeout: Some(Duration::from_secs(30)),
+            read_idle_timeout: None,
+            max_idle_connections: 4,
+            tcp_nodelay: false,
+            tcp_keepalive: None,
+            accept_header: Some("application/json".to_owned()),
+            dns_cache_ttl: Some(Duration::from_secs(60)),
+            unwrap_path: None,
+            lenient_fields: false,
+            write_buffer_size: 8 * 1024,
+            default_headers: Vec::new(),
+            host_override: None,
+            preflight: false,
+            max_buffer_size: 64 * 1024,
+            max_request_size: None,
+            proxy: None,
+            use_env_proxy: false,
+            id_prefix: None,
+            dry_run: false,
+            dry_run_result: serde_json::Value::Null,
+            compress_request: false,
+            compress_request_threshold: 1024,
+            ip_version: IpPreference::Any,
+            rate_limit: None,
+            error_array_fallback: false,
+        }
+    }
+
+}
+
+impl ClientConfig {
+
+    /// Start from the default settings, see `ClientConfigBuilder`
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+}
+
+/// IP versions a client connects over
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpPreference {
+    /// Every resolved address, in resolution order
+    #[default]
+    Any,
+    V4Only,
+    V6Only,
+}
+
+impl IpPreference {
+
+    pub(crate) fn allows(self, address: &SocketAddr) -> bool {
+        match self {
+            IpPreference::Any => true,
+            IpPreference::V4Only => address.is_ipv4(),
+            IpPreference::V6Only => address.is_ipv6(),
+        }
+    }
+
+}
+
+/// Chainable construction of `ClientConfig`, unset fields keep their defaults
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let config = jsonrpc_v2_client::ClientConfig::builder()
+///     .timeout(Some(Duration::from_secs(5)))
+///     .write_buffer_size(64 * 1024)
+///     .build();
+/// assert_eq!(config.timeout, Some(Duration::from_secs(5)));
+/// assert_eq!(config.write_buffer_size, 64 * 1024);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfigBuilder {
+    config: ClientConfig,
+}
+
+macro_rules! setters {
+    ($($(#[$meta:meta])* $field:ident: $ty:ty;)*) => {
+        $(
+            #[doc = concat!("Set `ClientConfig::", stringify!($field), "`")]
+            $(#[$meta])*
+            pub fn $field(mut self, $field: $ty) -> ClientConfigBuilder {
+                self.config.$field = $field;
+                self
+            }
+        )*
+    };
+}
+
+impl ClientConfigBuilder {
+
+    pub fn new() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+
+    setters! {
+        timeout: Option<Duration>;
+        read_idle_timeout: Option<Duration>;
+        max_idle_connections: usize;
+        tcp_nodelay: bool;
+        tcp_keepalive: Option<Duration>;
+        accept_header: Option<String>;
+        dns_cache_ttl: Option<Duration>;
+        unwrap_path: Option<String>;
+        lenient_fields: bool;
+        write_buffer_size: usize;
+        default_headers: Vec<(String, String)>;
+        host_override: Option<String>;
+        preflight: bool;
+        max_buffer_size: usize;
+        max_request_size: Option<usize>;
+        proxy: Option<crate::Proxy>;
+        use_env_proxy: bool;
+        id_prefix: Option<String>;
+        dry_run: bool;
+        dry_run_result: serde_json::Value;
+        compress_request: bool;
+        compress_request_threshold: usize;
+        ip_version: IpPreference;
+        rate_limit: Option<(u32, Duration)>;
+        error_array_fallback: bool;
+    }
+
+    pub fn build(self) -> ClientConfig {
+        self.config
+    }
+
+}
+
+/// Parse duration like `1500ms`, `2.5s` or `2.5`, plain numbers are seconds
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// assert_eq!(jsonrpc_v2_client::parse_duration("1500ms"), Ok(Duration::from_millis(1500)));
+/// assert_eq!(jsonrpc_v2_client::parse_duration("2.5s"), Ok(Duration::from_millis(2500)));
+/// assert!(jsonrpc_v2_client::parse_duration("abc").is_err());
+/// ```
+pub fn parse_duration(value: &str) -> Result<Duration, JsonRpcError> {
+
+    let invalid = || JsonRpcError::SerializationError(format!("invalid duration: {}", value));
+    let value = value.trim();
+
+    let (number, scale) = match value.strip_suffix("ms") {
+        Some(millis) => (millis, 1e-3),
+        None => (value.strip_suffix('s').unwrap_or(value), 1.0),
+    };
+
+    let number = number.trim().parse::<f64>().map_err(|_| invalid())?;
+
+    Duration::try_from_secs_f64(number * scale).map_err(|_| invalid())
+}
+
+/// Optional durations as fractional seconds, read from numbers or `parse_duration` strings
+mod seconds {
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum Seconds {
+        Number(f64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        duration.map(|duration| duration.as_secs_f64()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Option::<Seconds>::deserialize(deserializer)?.map(to_duration).transpose()
+    }
+
+    pub(super) fn to_duration<E: serde::de::Error>(seconds: Seconds) -> Result<Duration, E> {
+        match seconds {
+            Seconds::Number(seconds) => Duration::try_from_secs_f64(seconds).map_err(E::custom),
+            Seconds::Text(text) => parse_duration(&text).map_err(E::custom),
+        }
+    }
+
+}
+
+/// Optional rate limits as `[calls, window]`, the window read like `seconds`
+mod rate {
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(rate: &Option<(u32, Duration)>, serializer: S) -> Result<S::Ok, S::Error> {
+        rate.map(|(calls, window)| (calls, window.as_secs_f64())).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<(u32, Duration)>, D::Error> {
+        Option::<(u32, seconds::Seconds)>::deserialize(deserializer)?
+            .map(|(calls, window)| seconds::to_duration(window).map(|window| (calls, window)))
+            .transpose()
+    }
+
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_std::net::ToSocketAddrs;
+
+use crate::JsonRpcError;
+
+/// Custom resolution of `host:port` into socket addresses
+pub(crate) type Resolve = Box<dyn Fn(&str) -> io::Result<Vec<SocketAddr>> + Send + Sync>;
+
+/// Resolves `host:port` strings and caches the answers for a TTL
+#[derive(Default)]
+pub(crate) struct Resolver {
+    custom: Option<Resolve>,
+    cache: Mutex<HashMap<String, (Instant, Vec<SocketAddr>)>>,
+}
+
+impl Resolver {
+
+    pub fn with_custom(custom: Resolve) -> Resolver {
+        Resolver {
+            custom: Some(custom),
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Addresses for `authority`, served from cache while younger than `ttl`
+    pub async fn resolve(
+        &self,
+        authority: &str,
+        ttl: Option<Duration>,
+    ) -> Result<Vec<SocketAddr>, JsonRpcError> {
+
+        if let Some(ttl) = ttl {
+            if let Some((resolved_at, addresses)) = self.cache.lock().unwrap().get(authority) {
+                if resolved_at.elapsed() < ttl {
+                    return Ok(addresses.clone());
+                }
+            }
+        }
+
+        let addresses = match &self.custom {
+            Some(custom) => checked(authority, custom(authority))?,
+            None => lookup(authority).await?,
+        };
+
+        log::trace!(
+            target: "jsonrpc_v2_client",
+            "[jsonrpc_v2_client: resolved {} to {:?}]",
+            authority,
+            addresses
+        );
+
+        if ttl.is_some() {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(authority.to_owned(), (Instant::now(), addresses.clone()));
+        }
+
+        Ok(addresses)
+    }
+
+}
+
+/// Addresses for `authority` from the system resolver, uncached
+pub(crate) async fn lookup(authority: &str) -> Result<Vec<SocketAddr>, JsonRpcError> {
+    checked(authority, authority.to_socket_addrs().await.map(Iterator::collect))
+}
+
+/// Fail with `ResolutionError` unless `answer` for `authority` holds addresses
+fn checked(authority: &str, answer: io::Result<Vec<SocketAddr>>) -> Result<Vec<SocketAddr>, JsonRpcError> {
+
+    let addresses = answer.map_err(|error| JsonRpcError::ResolutionError(format!("{}: {}", authority, error)))?;
+
+    if addresses.is_empty() {
+        return Err(JsonRpcError::ResolutionError(format!(
+            "{}: no addresses resolved",
+            authority
+        )));
+    }
+
+    Ok(addresses)
+}
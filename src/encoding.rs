@@ -0,0 +1,69 @@
+use crate::{ClientConfig, JsonRpcError};
+
+/// `Accept-Encoding` value listing content codings this build can decode
+pub(crate) fn accept_encoding() -> Option<&'static str> {
+
+    if cfg!(all(feature = "gzip", feature = "deflate")) {
+        Some("gzip, deflate")
+    } else if cfg!(feature = "gzip") {
+        Some("gzip")
+    } else if cfg!(feature = "deflate") {
+        Some("deflate")
+    } else {
+        None
+    }
+}
+
+/// Undo `Content-Encoding` applied to response body
+pub(crate) fn decode(content_encoding: Option<&str>, body: Vec<u8>) -> Result<Vec<u8>, JsonRpcError> {
+
+    match content_encoding.map(|encoding| encoding.trim().to_ascii_lowercase()) {
+        None => Ok(body),
+        Some(encoding) if encoding.is_empty() || encoding == "identity" => Ok(body),
+        #[cfg(feature = "gzip")]
+        Some(encoding) if encoding == "gzip" => {
+            read_all(flate2::read::GzDecoder::new(body.as_slice()))
+        },
+        #[cfg(feature = "deflate")]
+        Some(encoding) if encoding == "deflate" => {
+            read_all(flate2::read::ZlibDecoder::new(body.as_slice()))
+        },
+        Some(encoding) => Err(JsonRpcError::response_error(format!(
+            "unsupported Content-Encoding: {}",
+            encoding
+        ))),
+    }
+}
+
+/// Gzip request `body` when `ClientConfig::compress_request` applies to it,
+/// `None` sends it as is
+#[cfg_attr(not(feature = "gzip"), allow(unused_variables))]
+pub(crate) fn compress_request(body: &[u8], config: &ClientConfig) -> Option<Vec<u8>> {
+
+    if !config.compress_request || body.len() < config.compress_request_threshold {
+        return None;
+    }
+
+    #[cfg(feature = "gzip")]
+    {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body).ok()?;
+        encoder.finish().ok()
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    None
+}
+
+#[cfg(any(feature = "gzip", feature = "deflate"))]
+fn read_all(mut decoder: impl std::io::Read) -> Result<Vec<u8>, JsonRpcError> {
+
+    let mut decoded = Vec::new();
+    decoder
+        .read_to_end(&mut decoded)
+        .map_err(|error| JsonRpcError::response_error(error.to_string()))?;
+
+    Ok(decoded)
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::RpcId;
+
+/// Errors produced while sending a request or interpreting its response
+///
+/// # Examples
+///
+/// ```
+/// let error = jsonrpc_v2_client::JsonRpcError::ConnectionError("connection refused".to_owned());
+/// println!("{}", error);
+/// // connection error: connection refused
+/// ```
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm:
+///
+/// ```
+/// use jsonrpc_v2_client::JsonRpcError;
+///
+/// fn is_transient(error: &JsonRpcError) -> bool {
+///     match error {
+///         JsonRpcError::ConnectionError(_) | JsonRpcError::Timeout => true,
+///         _ => false,
+///     }
+/// }
+///
+/// assert!(is_transient(&JsonRpcError::Timeout));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum JsonRpcError {
+    /// Service URL could not be parsed
+    InvalidUrl(String),
+    /// Connecting to the service or writing the request failed
+    ConnectionError(String),
+    /// Service host name could not be resolved to any address
+    ResolutionError(String),
+    /// Request could not be serialized or result could not be deserialized
+    SerializationError(String),
+    /// Response is not a valid HTTP / JSONRPC response, `id` is that of
+    /// the request when its result could not be extracted or validated
+    ResponseError {
+        message: String,
+        id: Option<RpcId>,
+    },
+    /// Server answered with a JSONRPC error object,
+    /// `id` is the id of the request that failed
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<serde_json::Value>,
+        id: Option<RpcId>,
+    },
+    /// Server answered with non-success HTTP status and no JSONRPC body
+    HttpStatus {
+        code: u16,
+        retry_after: Option<Duration>,
+    },
+    /// Exchange did not complete within configured timeout
+    Timeout,
+    /// Call made after `Client::shutdown`
+    ClientShutdown,
+    /// Endpoint did not allow our requests in `OPTIONS` preflight
+    Preflight(String),
+    /// Response `id` does not belong to the request that was sent
+    IdMismatch {
+        expected: serde_json::Value,
+        received: serde_json::Value,
+    },
+}
+
+impl fmt::Display for JsonRpcError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonRpcError::InvalidUrl(message) => write!(f, "invalid URL: {}", message),
+            JsonRpcError::ConnectionError(message) => write!(f, "connection error: {}", message),
+            JsonRpcError::ResolutionError(message) => write!(f, "resolution error: {}", message),
+            JsonRpcError::SerializationError(message) => write!(f, "serialization error: {}", message),
+            JsonRpcError::ResponseError { message, id: Some(id) } => {
+                write!(f, "response error (request {}): {}", id, message)
+            }
+            JsonRpcError::ResponseError { message, .. } => write!(f, "response error: {}", message),
+            JsonRpcError::Rpc { code, message, id: Some(id), .. } => {
+                write!(f, "rpc error {} (request {}): {}", code, id, message)
+            }
+            JsonRpcError::Rpc { code, message, .. } => write!(f, "rpc error {}: {}", code, message),
+            JsonRpcError::HttpStatus { code, .. } => write!(f, "HTTP status {}", code),
+            JsonRpcError::Timeout => write!(f, "request timed out"),
+            JsonRpcError::ClientShutdown => write!(f, "client shut down"),
+            JsonRpcError::Preflight(message) => write!(f, "preflight failed: {}", message),
+            JsonRpcError::IdMismatch { expected, received } => {
+                write!(f, "id mismatch: expected {}, received {}", expected, received)
+            }
+        }
+    }
+
+}
+
+impl JsonRpcError {
+
+    /// `ResponseError` not tied to any request
+    pub(crate) fn response_error(message: impl Into<String>) -> JsonRpcError {
+        JsonRpcError::ResponseError {
+            message: message.into(),
+            id: None,
+        }
+    }
+
+    /// Tag a `ResponseError` without id with the id of the request it answers
+    pub(crate) fn for_request(self, request: &RpcId) -> JsonRpcError {
+        match self {
+            JsonRpcError::ResponseError { message, id: None } => JsonRpcError::ResponseError {
+                message,
+                id: Some(request.clone()),
+            },
+            error => error,
+        }
+    }
+
+}
+
+impl std::error::Error for JsonRpcError {}
+
+/// Connection failures map to `NotConnected`, timeouts to `TimedOut`,
+/// everything else to `Other`; the original error is kept as source
+impl From<JsonRpcError> for std::io::Error {
+
+    fn from(error: JsonRpcError) -> std::io::Error {
+
+        let kind = match error {
+            JsonRpcError::ConnectionError(_) | JsonRpcError::ClientShutdown => std::io::ErrorKind::NotConnected,
+            JsonRpcError::Timeout => std::io::ErrorKind::TimedOut,
+            _ => std::io::ErrorKind::Other,
+        };
+
+        std::io::Error::new(kind, error)
+    }
+
+}
@@ -0,0 +1,35 @@
+/// HTTP response headers
+///
+/// Header names are matched case-insensitively, as required by HTTP.
+///
+/// # Examples
+///
+/// ```
+/// let headers = jsonrpc_v2_client::ResponseHeaders::new(vec![
+///     ("Content-Type".to_owned(), "application/json".to_owned()),
+/// ]);
+/// assert_eq!(headers.get("content-type"), Some("application/json"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResponseHeaders(Vec<(String, String)>);
+
+impl ResponseHeaders {
+
+    pub fn new(headers: Vec<(String, String)>) -> ResponseHeaders {
+        ResponseHeaders(headers)
+    }
+
+    /// Value of first header called `name`, ignoring case
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All headers in received order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+}
@@ -0,0 +1,201 @@
+use crate::RequestSigner;
+
+/// Signer sending hex encoded HMAC-SHA256 of `{timestamp}.{body}`
+/// in `X-Signature` and the timestamp in `X-Timestamp`
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_v2_client::{HmacSha256Signer, RequestSigner};
+///
+/// let signer = HmacSha256Signer::new(b"secret").with_header_names("X-Api-Signature", "X-Api-Timestamp");
+/// let headers = signer.sign(br#"{"jsonrpc":"2.0","method":"ping","id":"1"}"#, 1700000000);
+/// assert_eq!(headers[1], ("X-Api-Timestamp".to_owned(), "1700000000".to_owned()));
+/// ```
+#[derive(Clone)]
+pub struct HmacSha256Signer {
+    key: Vec<u8>,
+    signature_header: String,
+    timestamp_header: String,
+}
+
+impl HmacSha256Signer {
+
+    pub fn new(key: &[u8]) -> HmacSha256Signer {
+        HmacSha256Signer {
+            key: key.to_vec(),
+            signature_header: "X-Signature".to_owned(),
+            timestamp_header: "X-Timestamp".to_owned(),
+        }
+    }
+
+    /// Send signature and timestamp in headers named `signature` and `timestamp`
+    pub fn with_header_names(mut self, signature: &str, timestamp: &str) -> HmacSha256Signer {
+        self.signature_header = signature.to_owned();
+        self.timestamp_header = timestamp.to_owned();
+        self
+    }
+
+}
+
+impl std::fmt::Debug for HmacSha256Signer {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the key is a secret, keep it out of logs
+        f.debug_struct("HmacSha256Signer")
+            .field("signature_header", &self.signature_header)
+            .field("timestamp_header", &self.timestamp_header)
+            .finish_non_exhaustive()
+    }
+
+}
+
+impl RequestSigner for HmacSha256Signer {
+
+    fn sign(&self, body: &[u8], timestamp: u64) -> Vec<(String, String)> {
+
+        let timestamp = timestamp.to_string();
+        let mut message = Vec::with_capacity(timestamp.len() + 1 + body.len());
+        message.extend_from_slice(timestamp.as_bytes());
+        message.push(b'.');
+        message.extend_from_slice(body);
+
+        let signature: String = hmac_sha256(&self.key, &message)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        vec![
+            (self.signature_header.clone(), signature),
+            (self.timestamp_header.clone(), timestamp),
+        ]
+    }
+
+}
+
+/// HMAC-SHA256 as specified in RFC 2104
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+    inner.extend_from_slice(message);
+
+    let mut outer: Vec<u8> = block.iter().map(|byte| byte ^ 0x5c).collect();
+    outer.extend_from_slice(&sha256(&inner));
+
+    sha256(&outer)
+}
+
+const BLOCK_SIZE: usize = 64;
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 as specified in FIPS 180-4, small enough to spare a dependency
+fn sha256(message: &[u8]) -> [u8; 32] {
+
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % BLOCK_SIZE != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for chunk in padded.chunks_exact(BLOCK_SIZE) {
+
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(ROUND_CONSTANTS[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_sha256_vectors() {
+        assert_eq!(
+            hex(&super::sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&super::sha256(&[b'a'; 1000])),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        assert_eq!(
+            hex(&super::hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(&super::hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+}
@@ -0,0 +1,573 @@
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_std::future;
+use async_std::prelude::*;
+
+use crate::{encoding, APIKey, ClientConfig, JsonRpcError, ResponseHeaders, ServiceAddress};
+
+/// Size of a single read from the socket
+const READ_CHUNK_SIZE: usize = 4 * 1024;
+
+/// First pause before reading again from a stream answering `WouldBlock`
+const WOULD_BLOCK_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Longest pause between reads from a stream answering `WouldBlock`
+const MAX_WOULD_BLOCK_BACKOFF: Duration = Duration::from_millis(50);
+
+/// HTTP header name / value pairs in received order
+pub(crate) type Headers = Vec<(String, String)>;
+
+/// Parsed HTTP response
+#[derive(Clone, Debug)]
+pub(crate) struct HttpResponse {
+    pub status: u16,
+    pub headers: ResponseHeaders,
+    pub body: Vec<u8>,
+    /// Connection may carry another request after this response
+    pub keep_alive: bool,
+    /// Bytes the response took up as received, head included
+    pub size: usize,
+}
+
+impl HttpResponse {
+
+    /// Case-insensitive header lookup
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// Delay requested by `Retry-After`, given either as seconds or as HTTP-date
+    pub fn retry_after(&self) -> Option<Duration> {
+
+        let value = self.header("Retry-After")?;
+
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        let date = parse_http_date(value)?;
+
+        Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+
+    /// Successful response without a body, e.g. `204 No Content`
+    #[cfg(feature = "blocking")]
+    pub fn is_empty_success(&self) -> bool {
+        (200..300).contains(&self.status) && self.body.iter().all(u8::is_ascii_whitespace)
+    }
+
+}
+
+/// Request target naming the endpoint, absolute-form through an HTTP proxy
+///
+/// RFC 7230 section 5.3.2, the proxy learns the service from the target
+/// while `Host` still names it for the origin server.
+fn request_target(service_address: &ServiceAddress, config: &ClientConfig) -> String {
+
+    match &config.proxy {
+        Some(crate::Proxy::Http { .. }) => format!(
+            "{}://{}{}",
+            service_address.scheme.as_str(),
+            service_address.url,
+            service_address.endpoint
+        ),
+        _ => service_address.endpoint.clone(),
+    }
+}
+
+/// Build HTTP/1.1 POST request carrying `body` as JSON
+///
+/// `headers` are added after `config.default_headers`,
+/// replacing default headers of the same name.
+pub(crate) fn encode_request(
+    service_address: &ServiceAddress,
+    api_key: Option<&APIKey>,
+    config: &ClientConfig,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Vec<u8> {
+
+    let mut head = format!(
+        "POST {} HTTP/1.1\r\n\
+        Host: {}\r\n\
+        Content-Type: application/json\r\n\
+        User-Agent: jsonrpc_v2_client\r\n",
+        request_target(service_address, config),
+        config.host_override.as_deref().unwrap_or(&service_address.url),
+    );
+
+    if let Some(accept) = &config.accept_header {
+        head.push_str(&format!("Accept: {}\r\n", accept));
+    }
+
+    if let Some(accept_encoding) = encoding::accept_encoding() {
+        head.push_str(&format!("Accept-Encoding: {}\r\n", accept_encoding));
+    }
+
+    if let Some(authorization) = &service_address.authorization {
+        head.push_str(&format!("Authorization: {}\r\n", authorization));
+    }
+
+    if let Some(key_value) = api_key {
+        head.push_str(&format!("{}\r\n", key_value.as_header()));
+    }
+
+    let defaults = config
+        .default_headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .filter(|(name, _)| !headers.iter().any(|(own, _)| own.eq_ignore_ascii_case(name)));
+
+    for (name, value) in defaults.chain(headers.iter().copied()) {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    // always present, strict servers reject bodies without it even when empty
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+    let mut request = head.into_bytes();
+    request.extend_from_slice(body);
+    request
+}
+
+/// Build bodiless `OPTIONS` request for the endpoint, used to check it is alive
+pub(crate) fn encode_options(service_address: &ServiceAddress, config: &ClientConfig) -> Vec<u8> {
+
+    format!(
+        "OPTIONS {} HTTP/1.1\r\n\
+        Host: {}\r\n\
+        User-Agent: jsonrpc_v2_client\r\n\
+        Content-Length: 0\r\n\r\n",
+        request_target(service_address, config),
+        config.host_override.as_deref().unwrap_or(&service_address.url),
+    )
+    .into_bytes()
+}
+
+/// Build CORS preflight asking whether POST with `request_headers` is allowed
+pub(crate) fn encode_preflight(
+    service_address: &ServiceAddress,
+    config: &ClientConfig,
+    request_headers: &str,
+) -> Vec<u8> {
+
+    format!(
+        "OPTIONS {} HTTP/1.1\r\n\
+        Host: {}\r\n\
+        User-Agent: jsonrpc_v2_client\r\n\
+        Access-Control-Request-Method: POST\r\n\
+        Access-Control-Request-Headers: {}\r\n\
+        Content-Length: 0\r\n\r\n",
+        request_target(service_address, config),
+        config.host_override.as_deref().unwrap_or(&service_address.url),
+        request_headers,
+    )
+    .into_bytes()
+}
+
+/// Read a complete HTTP response from `stream`
+///
+/// The body is delimited by chunked transfer encoding or `Content-Length`
+/// when present, otherwise by the server closing the connection.
+/// The response is read into `buffer`, which becomes the body unless it is decoded.
+/// With `idle_timeout` set, a read receiving nothing for that long fails with `Timeout`.
+pub(crate) async fn read_response<S>(
+    stream: &mut S,
+    mut buffer: Vec<u8>,
+    idle_timeout: Option<Duration>,
+) -> Result<HttpResponse, JsonRpcError>
+where
+    S: async_std::io::Read + Unpin,
+{
+    buffer.clear();
+    let mut chunk = [0u8; READ_CHUNK_SIZE];
+    let mut backoff = WOULD_BLOCK_BACKOFF;
+
+    let frame = loop {
+
+        if let Some(frame) = frame_response(&buffer)? {
+            break frame;
+        }
+
+        let read = stream.read(&mut chunk);
+        let read = match idle_timeout {
+            Some(idle_timeout) => future::timeout(idle_timeout, read).await.map_err(|_| {
+                log::debug!(
+                    target: "jsonrpc_v2_client",
+                    "[jsonrpc_v2_client: no response bytes within {:?}]",
+                    idle_timeout
+                );
+                JsonRpcError::Timeout
+            })?,
+            None => read.await,
+        };
+
+        let size = match read {
+            Ok(size) => size,
+            // the read was cut short before any data arrived, try again
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            // stream reported not ready instead of waiting for readiness itself,
+            // poll it again after a growing pause rather than spinning
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                async_std::task::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_WOULD_BLOCK_BACKOFF);
+                continue;
+            }
+            Err(error) => {
+                log::error!(
+                    target: "jsonrpc_v2_client",
+                    "[jsonrpc_v2_client: error]\r\n{}",
+                    error
+                );
+                return Err(JsonRpcError::response_error(error.to_string()));
+            }
+        };
+
+        if size == 0 {
+            break closed_frame(&buffer)?;
+        }
+
+        buffer.extend_from_slice(&chunk[..size]);
+        backoff = WOULD_BLOCK_BACKOFF;
+    };
+
+    log::info!(
+        target: "jsonrpc_v2_client",
+        "[jsonrpc_v2_client: received response of len = {}]",
+        buffer.len(),
+    );
+
+    frame.into_response(buffer)
+}
+
+/// Response located in received bytes, not yet content-decoded
+#[derive(Debug)]
+pub(crate) struct Frame {
+    status: u16,
+    headers: Headers,
+    body: FrameBody,
+    /// Number of received bytes taken up by the response
+    pub end: usize,
+    /// Length was known from the head rather than the connection closing
+    delimited: bool,
+}
+
+#[derive(Debug)]
+enum FrameBody {
+    /// Body left in place, at this range of the received bytes
+    Raw(usize, usize),
+    Dechunked(Vec<u8>),
+}
+
+impl Frame {
+
+    /// Decode into response, `received` being the bytes the frame was found in,
+    /// reused as body when not dechunked
+    pub fn into_response(self, mut received: Vec<u8>) -> Result<HttpResponse, JsonRpcError> {
+
+        let close = self.headers.iter().any(|(key, value)| {
+            key.eq_ignore_ascii_case("Connection") && value.eq_ignore_ascii_case("close")
+        });
+        let http_10 = received.starts_with(b"HTTP/1.0");
+        let headers = ResponseHeaders::new(self.headers);
+        let body = match self.body {
+            FrameBody::Raw(start, end) => {
+                received.truncate(end);
+                received.drain(..start);
+                received
+            }
+            FrameBody::Dechunked(body) => body,
+        };
+        // transfer coding wraps content coding, so decompress only after dechunking
+        let body = encoding::decode(headers.get("Content-Encoding"), body)?;
+
+        Ok(HttpResponse {
+            status: self.status,
+            headers,
+            body,
+            keep_alive: self.delimited && !close && !http_10,
+            size: self.end,
+        })
+    }
+
+}
+
+/// Locate complete response at the start of `received`, `None` while more bytes are needed
+///
+/// Responses without `Content-Length` or chunked encoding end only when
+/// the connection closes, see `closed_frame`.
+pub(crate) fn frame_response(received: &[u8]) -> Result<Option<Frame>, JsonRpcError> {
+
+    let Some((status, headers, body_start)) = locate_head(received)? else {
+        return Ok(None);
+    };
+
+    // RFC 7230 section 3.3.3, these never carry a body whatever the headers say
+    let (body, end) = if status == 204 || status == 304 {
+        (FrameBody::Raw(body_start, body_start), body_start)
+    } else if is_chunked(&headers) {
+        match dechunk(&received[body_start..])? {
+            Some((body, length)) => (FrameBody::Dechunked(body), body_start + length),
+            None => return Ok(None),
+        }
+    } else if let Some(length) = content_length(&headers)? {
+        match body_start.checked_add(length) {
+            Some(end) if end <= received.len() => (FrameBody::Raw(body_start, end), end),
+            _ => return Ok(None),
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(Frame {
+        status,
+        headers,
+        body,
+        end,
+        delimited: true,
+    }))
+}
+
+/// Response in `received` once the connection closed, the body being
+/// whatever arrived unless it was delimited
+pub(crate) fn closed_frame(received: &[u8]) -> Result<Frame, JsonRpcError> {
+
+    if let Some(frame) = frame_response(received)? {
+        return Ok(frame);
+    }
+
+    match locate_head(received)? {
+        Some((status, headers, body_start)) => Ok(Frame {
+            status,
+            headers,
+            body: FrameBody::Raw(body_start, received.len()),
+            end: received.len(),
+            delimited: false,
+        }),
+        None => Err(JsonRpcError::response_error(
+            "incomplete HTTP response".to_owned(),
+        )),
+    }
+}
+
+/// Status, headers and body start of the response head in `received`,
+/// `None` while the head is incomplete
+fn locate_head(received: &[u8]) -> Result<Option<(u16, Headers, usize)>, JsonRpcError> {
+
+    if !starts_like_status_line(received) {
+        return Err(JsonRpcError::response_error(
+            "malformed HTTP response: no status line".to_owned(),
+        ));
+    }
+
+    match find_head_end(received) {
+        Some((end, body_start)) => {
+            let (status, headers) = parse_head(&received[..end])?;
+            Ok(Some((status, headers, body_start)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Whether `buffer` is, or may still become, a response with status line
+fn starts_like_status_line(buffer: &[u8]) -> bool {
+    let prefix = buffer.len().min(5);
+    buffer[..prefix] == b"HTTP/"[..prefix]
+}
+
+/// End of the head and start of the body, bare `\n` line endings are accepted
+fn find_head_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    buffer
+        .iter()
+        .enumerate()
+        .filter(|(_, byte)| **byte == b'\n')
+        .find_map(|(end, _)| match &buffer[end + 1..] {
+            [b'\n', ..] => Some((end, end + 2)),
+            [b'\r', b'\n', ..] => Some((end, end + 3)),
+            _ => None,
+        })
+}
+
+fn parse_head(head: &[u8]) -> Result<(u16, Headers), JsonRpcError> {
+
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.lines();
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| JsonRpcError::response_error("invalid HTTP status line".to_owned()))?;
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+
+    Ok((status, headers))
+}
+
+fn content_length(headers: &[(String, String)]) -> Result<Option<usize>, JsonRpcError> {
+
+    match headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("Content-Length"))
+    {
+        Some((_, value)) => value
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| JsonRpcError::response_error(format!("invalid Content-Length: {}", value))),
+        None => Ok(None),
+    }
+}
+
+fn is_chunked(headers: &[(String, String)]) -> bool {
+    headers.iter().any(|(key, value)| {
+        key.eq_ignore_ascii_case("Transfer-Encoding")
+            && value
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+    })
+}
+
+/// Decode chunked `body` into data and the length it took up,
+/// `None` while the final chunk or trailers are incomplete
+///
+/// Chunk extensions and trailer fields after the last chunk are skipped.
+fn dechunk(body: &[u8]) -> Result<Option<(Vec<u8>, usize)>, JsonRpcError> {
+
+    let invalid = || JsonRpcError::response_error("invalid chunked encoding".to_owned());
+    let mut decoded = Vec::new();
+    let mut position = 0;
+
+    loop {
+
+        let Some(line_end) = body[position..].iter().position(|byte| *byte == b'\n') else {
+            return Ok(None);
+        };
+        let line = String::from_utf8_lossy(&body[position..position + line_end]);
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| invalid())?;
+        position += line_end + 1;
+
+        if size == 0 {
+            break;
+        }
+
+        let data_end = position.checked_add(size).ok_or_else(invalid)?;
+        let Some(rest) = body.get(data_end..) else {
+            return Ok(None);
+        };
+        let delimiter = match rest {
+            [b'\r', b'\n', ..] => 2,
+            [b'\n', ..] => 1,
+            [] | [b'\r'] => return Ok(None),
+            _ => return Err(invalid()),
+        };
+
+        decoded.extend_from_slice(&body[position..data_end]);
+        position = data_end + delimiter;
+    }
+
+    // trailer fields end with an empty line
+    loop {
+        let Some(line_end) = body[position..].iter().position(|byte| *byte == b'\n') else {
+            return Ok(None);
+        };
+        let line = &body[position..position + line_end];
+        position += line_end + 1;
+
+        if line.is_empty() || line == b"\r" {
+            return Ok(Some((decoded, position)));
+        }
+    }
+}
+
+/// Parse IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+
+    let day: i64 = parts[1].parse().ok()?;
+    let month = MONTHS.iter().position(|month| *month == parts[2])? as i64 + 1;
+    let year: i64 = parts[3].parse().ok()?;
+
+    let time: Vec<i64> = parts[4]
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+
+    if time.len() != 3 {
+        return None;
+    }
+
+    // days since epoch for proleptic Gregorian calendar date
+    let shifted_year = if month <= 2 { year - 1 } else { year };
+    let era = shifted_year.div_euclid(400);
+    let year_of_era = shifted_year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + time[0] * 3_600 + time[1] * 60 + time[2];
+
+    u64::try_from(seconds)
+        .ok()
+        .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn test_empty_body_has_zero_content_length() {
+        use crate::{ClientConfig, ServiceAddress};
+
+        let address = ServiceAddress::new("127.0.0.1:8082", "/api");
+        let request = super::encode_request(&address, None, &ClientConfig::default(), &[], b"");
+        let request = String::from_utf8(request).unwrap();
+
+        assert!(request.ends_with("Content-Length: 0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_chunked_body_excludes_trailers() {
+        let response = concat!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: X-Checksum\r\n\r\n",
+            "11;name=value\r\n{\"jsonrpc\":\"2.0\",\r\n",
+            "14\r\n\"result\":1,\"id\":\"0\"}\r\n",
+            "0\r\nX-Checksum: abc\r\n\r\n",
+        );
+        let mut stream = async_std::io::Cursor::new(response.as_bytes().to_vec());
+        let response = async_std::task::block_on(super::read_response(&mut stream, Vec::new(), None)).unwrap();
+
+        assert_eq!(response.body, br#"{"jsonrpc":"2.0","result":1,"id":"0"}"#);
+        assert!(response.keep_alive);
+    }
+
+    #[test]
+    fn test_bare_newline_separators() {
+        let body = r#"{"jsonrpc":"2.0","result":1,"id":"0"}"#;
+        let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len());
+
+        for head in [head.clone(), head.replace("\r\n", "\n")] {
+            let mut stream = async_std::io::Cursor::new(format!("{}{}", head, body).into_bytes());
+            let response = async_std::task::block_on(super::read_response(&mut stream, Vec::new(), None)).unwrap();
+
+            assert_eq!(response.status, 200);
+            assert_eq!(response.headers.get("Content-Length"), Some(body.len().to_string().as_str()));
+            assert_eq!(response.body, body.as_bytes());
+        }
+    }
+
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::Serialize;
+
+/// Request identifier
+///
+/// JSONRPC allows strings, numbers and `null` as request id.
+/// Numbers must be integers: any JSON number with a fraction or exponent,
+/// even an integral one like `3.0`, is rejected when deserializing.
+///
+/// # Examples
+///
+/// ```
+/// let numeric = jsonrpc_v2_client::RpcId::from(1);
+/// let textual = jsonrpc_v2_client::RpcId::from("request-1");
+/// println!("{} {}", numeric, textual);
+/// // 1 "request-1"
+///
+/// let id: jsonrpc_v2_client::RpcId = serde_json::from_str("null").unwrap();
+/// assert_eq!(id, jsonrpc_v2_client::RpcId::Null);
+/// assert!(serde_json::from_str::<jsonrpc_v2_client::RpcId>("3.0").is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum RpcId {
+    Number(i64),
+    String(String),
+    /// Sent by servers answering requests whose id could not be determined
+    Null,
+}
+
+impl RpcId {
+
+    /// Id as JSON value
+    pub(crate) fn to_value(&self) -> serde_json::Value {
+        match self {
+            RpcId::Number(number) => serde_json::Value::from(*number),
+            RpcId::String(string) => serde_json::Value::from(string.as_str()),
+            RpcId::Null => serde_json::Value::Null,
+        }
+    }
+
+}
+
+impl fmt::Display for RpcId {
+
+    /// Formats id the way it appears on the wire
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcId::Number(number) => write!(f, "{}", number),
+            RpcId::String(string) => write!(f, "{:?}", string),
+            RpcId::Null => write!(f, "null"),
+        }
+    }
+
+}
+
+impl<'de> Deserialize<'de> for RpcId {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RpcId, D::Error> {
+        deserializer.deserialize_any(RpcIdVisitor)
+    }
+
+}
+
+struct RpcIdVisitor;
+
+impl<'de> Visitor<'de> for RpcIdVisitor {
+    type Value = RpcId;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "integer, string or null request id")
+    }
+
+    fn visit_i64<E: de::Error>(self, number: i64) -> Result<RpcId, E> {
+        Ok(RpcId::Number(number))
+    }
+
+    fn visit_u64<E: de::Error>(self, number: u64) -> Result<RpcId, E> {
+        i64::try_from(number)
+            .map(RpcId::Number)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(number), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, number: f64) -> Result<RpcId, E> {
+        Err(E::invalid_type(de::Unexpected::Float(number), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<RpcId, E> {
+        Ok(RpcId::from(string))
+    }
+
+    fn visit_string<E: de::Error>(self, string: String) -> Result<RpcId, E> {
+        Ok(RpcId::String(string))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<RpcId, E> {
+        Ok(RpcId::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<RpcId, E> {
+        Ok(RpcId::Null)
+    }
+}
+
+impl From<i64> for RpcId {
+
+    fn from(number: i64) -> RpcId {
+        RpcId::Number(number)
+    }
+
+}
+
+impl From<&str> for RpcId {
+
+    fn from(string: &str) -> RpcId {
+        RpcId::String(string.to_owned())
+    }
+
+}
+
+impl From<String> for RpcId {
+
+    fn from(string: String) -> RpcId {
+        RpcId::String(string)
+    }
+
+}
+
+/// Source of request ids used by `Client`
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_v2_client::{IdGenerator, RpcId};
+///
+/// struct Constant;
+///
+/// impl IdGenerator for Constant {
+///     fn next_id(&self) -> RpcId {
+///         RpcId::from("constant")
+///     }
+/// }
+///
+/// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+/// let client = jsonrpc_v2_client::Client::new(service_address).with_id_generator(Constant);
+/// ```
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> RpcId;
+}
+
+/// Default id generator producing `"1"`, `"2"`, ...
+#[derive(Debug)]
+pub struct CounterIdGenerator {
+    next: AtomicU64,
+}
+
+impl Default for CounterIdGenerator {
+
+    fn default() -> CounterIdGenerator {
+        CounterIdGenerator {
+            next: AtomicU64::new(1),
+        }
+    }
+
+}
+
+impl IdGenerator for CounterIdGenerator {
+
+    fn next_id(&self) -> RpcId {
+        RpcId::String(self.next.fetch_add(1, Ordering::Relaxed).to_string())
+    }
+
+}
+
+/// Id generator producing random version 4 UUID strings
+#[cfg(feature = "uuid")]
+#[derive(Debug, Default)]
+pub struct UuidIdGenerator;
+
+#[cfg(feature = "uuid")]
+impl IdGenerator for UuidIdGenerator {
+
+    fn next_id(&self) -> RpcId {
+        RpcId::String(uuid::Uuid::new_v4().to_string())
+    }
+
+}
@@ -0,0 +1,24 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Compile-time description of a remote method
+///
+/// Binds method name, parameter type and result type together
+/// so calls made with `Client::invoke` are checked by the compiler.
+///
+/// # Examples
+///
+/// ```
+/// struct Mul;
+///
+/// impl jsonrpc_v2_client::RpcMethod for Mul {
+///     const NAME: &'static str = "mul";
+///     type Params = [f64; 2];
+///     type Output = f64;
+/// }
+/// ```
+pub trait RpcMethod {
+    const NAME: &'static str;
+    type Params: Serialize;
+    type Output: DeserializeOwned;
+}
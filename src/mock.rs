@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::ServiceAddress;
+
+/// Method handler receiving `params` and returning `result`
+/// or JSONRPC error object
+type Handler = Box<dyn Fn(&Value) -> Result<Value, Value> + Send + Sync>;
+
+/// In-process JSONRPC server for tests
+///
+/// Binds ephemeral port on 127.0.0.1 and answers requests with
+/// canned responses keyed by method name.
+/// Unknown methods are answered with `-32601 Method not found`.
+/// The server stops when dropped.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_v2_client::{Client, MockRpcServer, Params};
+///
+/// let server = MockRpcServer::new()
+///     .result("ping", serde_json::json!("pong"))
+///     .start();
+///
+/// let client = Client::new(server.service_address());
+/// let pong: String = async_std::task::block_on(client.call("ping", Params(["hello"]))).unwrap();
+/// assert_eq!(pong, "pong");
+/// ```
+#[derive(Default)]
+pub struct MockRpcServer {
+    handlers: HashMap<String, Handler>,
+}
+
+impl MockRpcServer {
+
+    pub fn new() -> MockRpcServer {
+        MockRpcServer::default()
+    }
+
+    /// Answer `method` with fixed `result`
+    pub fn result(self, method: &str, result: Value) -> MockRpcServer {
+        self.handler(method, move |_| Ok(result.clone()))
+    }
+
+    /// Answer `method` with fixed JSONRPC error
+    pub fn error(self, method: &str, code: i64, message: &str) -> MockRpcServer {
+        let error = json!({"code": code, "message": message});
+        self.handler(method, move |_| Err(error.clone()))
+    }
+
+    /// Answer `method` with result or error object computed from params
+    pub fn handler<F>(mut self, method: &str, handler: F) -> MockRpcServer
+    where
+        F: Fn(&Value) -> Result<Value, Value> + Send + Sync + 'static,
+    {
+        self.handlers.insert(method.to_owned(), Box::new(handler));
+        self
+    }
+
+    /// Bind ephemeral port and serve requests in background threads
+    pub fn start(self) -> RunningMockRpcServer {
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let address = listener.local_addr().expect("mock server address");
+        let handlers = Arc::new(self.handlers);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let state = (requests.clone(), stopped.clone());
+        thread::spawn(move || {
+            let (requests, stopped) = state;
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let handlers = handlers.clone();
+                    let requests = requests.clone();
+                    thread::spawn(move || serve_connection(stream, &handlers, &requests));
+                }
+            }
+        });
+
+        RunningMockRpcServer {
+            address,
+            requests,
+            stopped,
+        }
+    }
+
+}
+
+/// Handle to started `MockRpcServer`
+#[derive(Debug)]
+pub struct RunningMockRpcServer {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl RunningMockRpcServer {
+
+    /// Bound socket address
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Address for sending requests to this server, endpoint is `/api`
+    pub fn service_address(&self) -> ServiceAddress {
+        ServiceAddress::new(&self.address.to_string(), "/api")
+    }
+
+    /// Raw HTTP requests received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+}
+
+impl Drop for RunningMockRpcServer {
+
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wake up accept loop so it can observe the flag
+        let _ = TcpStream::connect(self.address);
+    }
+
+}
+
+fn serve_connection(
+    mut stream: TcpStream,
+    handlers: &HashMap<String, Handler>,
+    requests: &Mutex<Vec<String>>,
+) {
+    while let Some((raw, body)) = read_request(&mut stream) {
+
+        requests.lock().unwrap().push(raw);
+
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Ok(Value::Array(calls)) => {
+                let responses: Vec<Value> = calls
+                    .iter()
+                    .filter_map(|call| answer(call, handlers))
+                    .collect();
+                if responses.is_empty() { None } else { Some(Value::Array(responses)) }
+            },
+            Ok(call) => answer(&call, handlers),
+            Err(_) => Some(json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32700, "message": "Parse error"},
+                "id": null,
+            })),
+        };
+
+        let written = match response {
+            Some(response) => {
+                let body = response.to_string();
+                stream.write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .as_bytes(),
+                )
+            },
+            None => stream.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n"),
+        };
+
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+/// Response object for single `call`, `None` for notifications
+fn answer(call: &Value, handlers: &HashMap<String, Handler>) -> Option<Value> {
+
+    let id = call.get("id")?.clone();
+    let method = call["method"].as_str().unwrap_or_default();
+    let params = call.get("params").cloned().unwrap_or(Value::Null);
+
+    let outcome = match handlers.get(method) {
+        Some(handler) => handler(&params),
+        None => Err(json!({"code": -32601, "message": "Method not found"})),
+    };
+
+    Some(match outcome {
+        Ok(result) => json!({"jsonrpc": "2.0", "result": result, "id": id}),
+        Err(error) => json!({"jsonrpc": "2.0", "error": error, "id": id}),
+    })
+}
+
+/// Read one HTTP request, returning it raw and its body
+fn read_request(stream: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4 * 1024];
+
+    loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buffer[..end]).to_string();
+            let length = head
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("Content-Length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+
+            if buffer.len() >= end + 4 + length {
+                buffer.truncate(end + 4 + length);
+                let body = buffer[end + 4..].to_vec();
+                return Some((String::from_utf8_lossy(&buffer).to_string(), body));
+            }
+        }
+
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return None,
+            Ok(size) => buffer.extend_from_slice(&chunk[..size]),
+        }
+    }
+}
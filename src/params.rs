@@ -0,0 +1,221 @@
+use std::fmt;
+
+use base64::Engine;
+use serde::ser::{self, Impossible, Serializer};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::JsonRpcError;
+
+/// Serialized name identifying `NoParams`, chosen so it cannot clash with user types
+const NO_PARAMS: &str = "$jsonrpc_v2_client::NoParams";
+
+/// Marker for requests sent without `params` member
+///
+/// Created by `Params::omitted()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename = "$jsonrpc_v2_client::NoParams")]
+pub struct NoParams;
+
+/// Binary data sent as base64 string, e.g. inside `Params`
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_v2_client::{Base64Bytes, Params};
+///
+/// let params = Params([Base64Bytes(vec![0xde, 0xad, 0xbe, 0xef])]);
+/// assert_eq!(serde_json::to_string(&params).unwrap(), r#"["3q2+7w=="]"#);
+///
+/// let Params([blob]): Params<[Base64Bytes; 1]> = serde_json::from_str(r#"["3q2+7w=="]"#).unwrap();
+/// assert_eq!(blob.0, vec![0xde, 0xad, 0xbe, 0xef]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Base64Bytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for Base64Bytes {
+
+    fn from(bytes: Vec<u8>) -> Base64Bytes {
+        Base64Bytes(bytes)
+    }
+
+}
+
+impl Serialize for Base64Bytes {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(&self.0))
+    }
+
+}
+
+impl<'de> Deserialize<'de> for Base64Bytes {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Base64Bytes, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map(Base64Bytes)
+            .map_err(serde::de::Error::custom)
+    }
+
+}
+
+/// Whether `value` is `NoParams`
+///
+/// Runs `value` through a serializer which only recognizes the marker
+/// and bails out on the first call for anything else.
+pub(crate) fn is_omitted<T: Serialize + ?Sized>(value: &T) -> bool {
+    matches!(value.serialize(Probe), Ok(true))
+}
+
+/// Reject scalar `params`, the specification requires array or object
+///
+/// `Value::Null` stands for omitted params and is accepted.
+pub(crate) fn check_structured(params: &serde_json::Value) -> Result<(), JsonRpcError> {
+
+    match params {
+        serde_json::Value::Null | serde_json::Value::Array(_) | serde_json::Value::Object(_) => Ok(()),
+        _ => Err(JsonRpcError::SerializationError(
+            "params must be array or object".to_owned(),
+        )),
+    }
+}
+
+struct Probe;
+
+#[derive(Debug)]
+struct NotOmitted;
+
+impl fmt::Display for NotOmitted {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not omitted")
+    }
+
+}
+
+impl std::error::Error for NotOmitted {}
+
+impl ser::Error for NotOmitted {
+
+    fn custom<M: fmt::Display>(_: M) -> NotOmitted {
+        NotOmitted
+    }
+
+}
+
+macro_rules! not_omitted {
+    ($($method:ident($($ty:ty),*);)*) => {
+        $(
+            fn $method(self $(, _: $ty)*) -> Result<bool, NotOmitted> {
+                Ok(false)
+            }
+        )*
+    };
+}
+
+impl Serializer for Probe {
+    type Ok = bool;
+    type Error = NotOmitted;
+    type SerializeSeq = Impossible<bool, NotOmitted>;
+    type SerializeTuple = Impossible<bool, NotOmitted>;
+    type SerializeTupleStruct = Impossible<bool, NotOmitted>;
+    type SerializeTupleVariant = Impossible<bool, NotOmitted>;
+    type SerializeMap = Impossible<bool, NotOmitted>;
+    type SerializeStruct = Impossible<bool, NotOmitted>;
+    type SerializeStructVariant = Impossible<bool, NotOmitted>;
+
+    not_omitted! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<bool, NotOmitted> {
+        Ok(name == NO_PARAMS)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<bool, NotOmitted> {
+        Ok(false)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<bool, NotOmitted> {
+        Ok(false)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<bool, NotOmitted> {
+        Ok(false)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, NotOmitted> {
+        Err(NotOmitted)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, NotOmitted> {
+        Err(NotOmitted)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, NotOmitted> {
+        Err(NotOmitted)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, NotOmitted> {
+        Err(NotOmitted)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, NotOmitted> {
+        Err(NotOmitted)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, NotOmitted> {
+        Err(NotOmitted)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, NotOmitted> {
+        Err(NotOmitted)
+    }
+}
@@ -0,0 +1,87 @@
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use async_std::net::TcpStream;
+
+/// Open connection together with its peer address
+#[derive(Debug)]
+pub(crate) struct Connection {
+    pub stream: TcpStream,
+    pub peer: SocketAddr,
+}
+
+/// Upper bound for the number of idle buffers kept by `BufferPool`
+const MAX_IDLE_BUFFERS: usize = 16;
+
+/// Idle keep-alive connections available for reuse
+#[derive(Debug, Default)]
+pub(crate) struct Pool {
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl Pool {
+
+    /// Take most recently used idle connection
+    pub fn checkout(&self) -> Option<Connection> {
+        self.idle.lock().unwrap().pop()
+    }
+
+    /// Keep `connection` for reuse unless `max_idle` connections are already kept,
+    /// in which case it is handed back
+    pub fn checkin(&self, connection: Connection, max_idle: usize) -> Result<(), Connection> {
+        let mut idle = self.idle.lock().unwrap();
+
+        if idle.len() >= max_idle {
+            return Err(connection);
+        }
+
+        idle.push(connection);
+        Ok(())
+    }
+
+    /// Take all idle connections
+    pub fn drain(&self) -> Vec<Connection> {
+        std::mem::take(&mut *self.idle.lock().unwrap())
+    }
+
+}
+
+/// Response buffers reused between calls to spare allocations
+#[derive(Debug, Default)]
+pub(crate) struct BufferPool {
+    idle: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+
+    /// Take idle buffer or a new empty one
+    pub fn checkout(&self) -> Vec<u8> {
+        self.idle.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    /// Keep `buffer` for reuse unless its capacity exceeds `max_size`
+    pub fn checkin(&self, mut buffer: Vec<u8>, max_size: usize) {
+
+        if buffer.capacity() == 0 || buffer.capacity() > max_size {
+            return;
+        }
+
+        let mut idle = self.idle.lock().unwrap();
+
+        if idle.len() < MAX_IDLE_BUFFERS {
+            buffer.clear();
+            idle.push(buffer);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn idle(&self) -> Vec<(*const u8, usize)> {
+        self.idle
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|buffer| (buffer.as_ptr(), buffer.capacity()))
+            .collect()
+    }
+
+}
@@ -0,0 +1,18 @@
+//! Commonly used types
+//!
+//! # Examples
+//!
+//! ```
+//! use jsonrpc_v2_client::prelude::*;
+//!
+//! let request = Request::new("add", Params([10.5, 20.5]), "0");
+//! let address = ServiceAddress::new("127.0.0.1:8082", "/api");
+//! let client = Client::new(address).with_config(ClientConfig::default());
+//! ```
+
+#[cfg(feature = "blocking")]
+pub use crate::{BlockingClient, RetryPolicy};
+pub use crate::{
+    APIKey, Base64Bytes, Batch, Client, ClientConfig, ClientConfigBuilder, DynRequest, IdGenerator, JsonRpcError, NoParams,
+    Params, Request, RequestSigner, RpcId, RpcMethod, ServiceAddress,
+};
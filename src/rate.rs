@@ -0,0 +1,113 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket holding up to `calls` tokens, refilled at `calls` per `window`
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// Tokens left and when they were counted, `None` until the first call
+    state: Mutex<Option<(f64, Instant)>>,
+    /// Clock the refill is measured with
+    now: fn() -> Instant,
+}
+
+impl Default for RateLimiter {
+
+    fn default() -> Self {
+        Self::with_clock(Instant::now)
+    }
+
+}
+
+impl RateLimiter {
+
+    /// Bucket measuring the refill with `now` instead of the system clock
+    pub fn with_clock(now: fn() -> Instant) -> Self {
+        Self { state: Mutex::new(None), now }
+    }
+
+    /// Take one token, waiting until the bucket refilled enough for it
+    pub async fn acquire(&self, calls: u32, window: Duration) {
+
+        while let Err(wait) = self.try_take(calls, window) {
+            async_std::task::sleep(wait).await;
+        }
+    }
+
+    /// Take one token, or tell how long until the bucket holds one
+    pub fn try_take(&self, calls: u32, window: Duration) -> Result<(), Duration> {
+
+        let capacity = f64::from(calls.max(1));
+        let per_second = capacity / window.as_secs_f64().max(f64::EPSILON);
+
+        let mut state = self.state.lock().unwrap();
+        let now = (self.now)();
+        let (tokens, counted_at) = state.unwrap_or((capacity, now));
+        let tokens = (tokens + now.saturating_duration_since(counted_at).as_secs_f64() * per_second).min(capacity);
+
+        if tokens >= 1.0 {
+            *state = Some((tokens - 1.0, now));
+            return Ok(());
+        }
+
+        *state = Some((tokens, now));
+        Err(Duration::from_secs_f64((1.0 - tokens) / per_second))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    thread_local! {
+        static NOW: Cell<Option<Instant>> = const { Cell::new(None) };
+    }
+
+    fn fake_now() -> Instant {
+        NOW.with(|now| *now.get().get_or_insert_with(Instant::now))
+    }
+
+    fn advance(by: Duration) {
+        let later = fake_now() + by;
+        NOW.with(|now| now.set(Some(later)));
+    }
+
+    /// Wait rounded to whole milliseconds, the refill math is in floats
+    fn wait_ms(taken: Result<(), Duration>) -> Result<(), u128> {
+        taken.map_err(|wait| (wait.as_secs_f64() * 1000.0).round() as u128)
+    }
+
+    #[test]
+    fn test_bucket_refills_as_clock_advances() {
+        let limiter = super::RateLimiter::with_clock(fake_now);
+        let window = Duration::from_millis(100);
+
+        // two calls from the full bucket, then one per 50ms refill
+        assert_eq!(limiter.try_take(2, window), Ok(()));
+        assert_eq!(limiter.try_take(2, window), Ok(()));
+        assert_eq!(wait_ms(limiter.try_take(2, window)), Err(50));
+
+        advance(Duration::from_millis(20));
+        assert_eq!(wait_ms(limiter.try_take(2, window)), Err(30));
+
+        advance(Duration::from_millis(30));
+        assert_eq!(limiter.try_take(2, window), Ok(()));
+        assert!(limiter.try_take(2, window).is_err());
+    }
+
+    #[test]
+    fn test_bucket_never_holds_more_than_calls() {
+        let limiter = super::RateLimiter::with_clock(fake_now);
+        let window = Duration::from_millis(100);
+
+        assert_eq!(limiter.try_take(2, window), Ok(()));
+        advance(Duration::from_secs(60));
+
+        assert_eq!(limiter.try_take(2, window), Ok(()));
+        assert_eq!(limiter.try_take(2, window), Ok(()));
+        assert!(limiter.try_take(2, window).is_err());
+    }
+
+}
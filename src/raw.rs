@@ -0,0 +1,218 @@
+use std::fmt;
+use std::time::Duration;
+
+use async_std::future;
+use async_std::io::BufReader;
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+use async_std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::client::Call;
+use crate::response::{check_id, extract_result};
+use crate::{CounterIdGenerator, IdGenerator, JsonRpcError, Params, JSONRPC_VERSION};
+
+/// How messages are delimited on a raw TCP connection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Framing {
+    /// Each message is a single line of JSON ended by `\n`
+    #[default]
+    Newline,
+    /// Each message is preceded by `Content-Length: N\r\n\r\n`,
+    /// as in the Language Server Protocol
+    ContentLength,
+}
+
+/// JSONRPC client for servers speaking JSON directly over TCP, without HTTP
+///
+/// Messages are delimited according to `Framing`. One connection is opened
+/// on the first call and reused for later calls, which are sent one at a time.
+///
+/// # Examples
+///
+/// ``` no_run
+/// # async_std::task::block_on(async {
+/// let client = jsonrpc_v2_client::RawTcpClient::new("127.0.0.1:7000");
+/// let product: f64 = client.call("mul", jsonrpc_v2_client::Params([2.5, 3.5])).await.unwrap();
+/// println!("{}", product);
+/// # });
+/// ```
+pub struct RawTcpClient {
+    address: String,
+    framing: Framing,
+    timeout: Option<Duration>,
+    id_generator: Box<dyn IdGenerator>,
+    connection: Mutex<Option<BufReader<TcpStream>>>,
+}
+
+impl RawTcpClient {
+
+    /// Client for the server at `address`, e.g. `127.0.0.1:7000`
+    pub fn new(address: &str) -> RawTcpClient {
+        RawTcpClient {
+            address: address.to_owned(),
+            framing: Framing::default(),
+            timeout: Some(Duration::from_secs(30)),
+            id_generator: Box::new(CounterIdGenerator::default()),
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Delimit messages with `framing`
+    pub fn with_framing(mut self, framing: Framing) -> RawTcpClient {
+        self.framing = framing;
+        self
+    }
+
+    /// Limit for each call, `None` waits forever
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> RawTcpClient {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Generate request ids with `id_generator`
+    pub fn with_id_generator(mut self, id_generator: impl IdGenerator + 'static) -> RawTcpClient {
+        self.id_generator = Box::new(id_generator);
+        self
+    }
+
+    /// Call `method` and deserialize its result into `R`
+    pub async fn call<P, R>(&self, method: &str, params: Params<P>) -> Result<R, JsonRpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.id_generator.next_id();
+        let call = Call {
+            jsonrpc: JSONRPC_VERSION,
+            method,
+            params: &params,
+            id: &id,
+        };
+        let body = serde_json::to_vec(&call).map_err(|error| JsonRpcError::SerializationError(error.to_string()))?;
+
+        let exchange = self.exchange(&body);
+        let response = match self.timeout {
+            Some(timeout) => future::timeout(timeout, exchange)
+                .await
+                .map_err(|_| JsonRpcError::Timeout)?,
+            None => exchange.await,
+        }?;
+
+        let response: serde_json::Value =
+            serde_json::from_slice(&response).map_err(|error| JsonRpcError::response_error(error.to_string()))?;
+
+        check_id(&response, &id)?;
+        extract_result(response, &id)
+    }
+
+    /// Send framed `body` and read the framed response
+    ///
+    /// The connection is kept only after a complete exchange, one left
+    /// by a failed or cancelled call could hold part of a response.
+    async fn exchange(&self, body: &[u8]) -> Result<Vec<u8>, JsonRpcError> {
+
+        let mut connection = self.connection.lock().await;
+
+        let mut stream = match connection.take() {
+            Some(stream) => stream,
+            None => TcpStream::connect(&self.address)
+                .await
+                .map(BufReader::new)
+                .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?,
+        };
+
+        let response = match self.framing {
+            Framing::Newline => exchange_line(&mut stream, body).await?,
+            Framing::ContentLength => exchange_with_length(&mut stream, body).await?,
+        };
+
+        *connection = Some(stream);
+
+        Ok(response)
+    }
+
+}
+
+/// Write `body` as one line and read one line back
+async fn exchange_line(stream: &mut BufReader<TcpStream>, body: &[u8]) -> Result<Vec<u8>, JsonRpcError> {
+
+    let mut message = body.to_vec();
+    message.push(b'\n');
+    stream
+        .get_mut()
+        .write_all(&message)
+        .await
+        .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?;
+
+    let mut line = Vec::new();
+    let size = stream
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|error| JsonRpcError::response_error(error.to_string()))?;
+
+    if size == 0 || line.last() != Some(&b'\n') {
+        return Err(JsonRpcError::response_error("connection closed before response".to_owned()));
+    }
+
+    Ok(line)
+}
+
+/// Write `body` after a `Content-Length` header and read a message framed the same way
+///
+/// Other headers of the response, e.g. `Content-Type`, are skipped.
+async fn exchange_with_length(stream: &mut BufReader<TcpStream>, body: &[u8]) -> Result<Vec<u8>, JsonRpcError> {
+
+    let mut message = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    message.extend_from_slice(body);
+    stream
+        .get_mut()
+        .write_all(&message)
+        .await
+        .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?;
+
+    let read_error = |error: std::io::Error| JsonRpcError::response_error(error.to_string());
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await.map_err(read_error)? == 0 {
+            return Err(JsonRpcError::response_error("connection closed before response".to_owned()));
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                let value = value.trim();
+                length = Some(value.parse::<usize>().map_err(|_| {
+                    JsonRpcError::response_error(format!("invalid Content-Length: {}", value))
+                })?);
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| JsonRpcError::response_error("response without Content-Length".to_owned()))?;
+    let mut response = vec![0; length];
+    stream.read_exact(&mut response).await.map_err(read_error)?;
+
+    Ok(response)
+}
+
+impl fmt::Debug for RawTcpClient {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawTcpClient")
+            .field("address", &self.address)
+            .field("framing", &self.framing)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+
+}
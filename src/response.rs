@@ -0,0 +1,174 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{JsonRpcError, RpcId};
+
+/// Turn JSONRPC response object into the deserialized `result`
+/// or the `Rpc` error carried in `error`, errors tagged with request `id`
+///
+/// `"error": null` next to a `result` is common enough to be read as absent.
+pub(crate) fn extract_result<R: DeserializeOwned>(
+    mut response: Value,
+    id: &RpcId,
+) -> Result<R, JsonRpcError> {
+
+    let object = response
+        .as_object_mut()
+        .ok_or_else(|| JsonRpcError::response_error("response is not a JSON object").for_request(id))?;
+
+    let error = object.remove("error").filter(|error| !error.is_null());
+
+    match (object.remove("result"), error) {
+
+        (Some(_), Some(_)) => {
+            Err(JsonRpcError::response_error("response contains both result and error").for_request(id))
+        }
+        (Some(result), None) => serde_json::from_value(result)
+            .map_err(|error| JsonRpcError::SerializationError(error.to_string())),
+        (None, Some(error)) => Err(rpc_error(error, Some(id.clone()))),
+        (None, None) => {
+            Err(JsonRpcError::response_error("response contains neither result nor error").for_request(id))
+        }
+    }
+}
+
+/// Like `check_id` followed by `extract_result`, except that a response
+/// with neither `result` nor `error`, e.g. a heartbeat, yields `None`
+///
+/// Heartbeats may leave out the `id`, its value is checked only when present.
+#[cfg(feature = "blocking")]
+pub(crate) fn extract_optional<R: DeserializeOwned>(
+    response: Value,
+    id: &RpcId,
+) -> Result<Option<R>, JsonRpcError> {
+
+    let is_heartbeat = response
+        .as_object()
+        .is_some_and(|object| !object.contains_key("result") && !object.contains_key("error"));
+
+    if !is_heartbeat || response.get("id").is_some() {
+        check_id(&response, id)?;
+    }
+
+    if is_heartbeat {
+        return Ok(None);
+    }
+
+    extract_result(response, id).map(Some)
+}
+
+/// Like `extract_result`, deserializing an array `result` element-wise
+/// and wrapping any other `result` into a one-element vector
+#[cfg(feature = "blocking")]
+pub(crate) fn extract_vec<R: DeserializeOwned>(response: Value, id: &RpcId) -> Result<Vec<R>, JsonRpcError> {
+
+    let result = match extract_result(response, id)? {
+        Value::Array(items) => items,
+        item => vec![item],
+    };
+
+    result
+        .into_iter()
+        .map(|item| serde_json::from_value(item).map_err(|error| JsonRpcError::SerializationError(error.to_string())))
+        .collect()
+}
+
+/// Drill into gateway envelope along dotted `path`
+pub(crate) fn unwrap_envelope(mut response: Value, path: &str) -> Result<Value, JsonRpcError> {
+
+    for key in path.split('.') {
+        response = match response.as_object_mut().and_then(|object| object.remove(key)) {
+            Some(inner) => inner,
+            None => {
+                return Err(JsonRpcError::response_error(format!(
+                    "response envelope has no `{}` member",
+                    path
+                )))
+            }
+        };
+    }
+
+    Ok(response)
+}
+
+/// Rename `jsonrpc`, `result`, `error` and `id` members spelled in
+/// another letter case to their specified names, in batches per item
+pub(crate) fn normalize_fields(response: &mut Value) {
+
+    const FIELDS: [&str; 4] = ["jsonrpc", "result", "error", "id"];
+
+    let objects: Vec<&mut serde_json::Map<String, Value>> = match response {
+        Value::Object(object) => vec![object],
+        Value::Array(items) => items.iter_mut().filter_map(Value::as_object_mut).collect(),
+        _ => return,
+    };
+
+    for object in objects {
+        let misspelled: Vec<(String, &str)> = object
+            .keys()
+            .filter_map(|key| {
+                FIELDS
+                    .iter()
+                    .find(|field| key != *field && key.eq_ignore_ascii_case(field))
+                    .map(|field| (key.clone(), *field))
+            })
+            .collect();
+
+        for (key, field) in misspelled {
+            if let Some(value) = object.remove(&key) {
+                object.entry(field).or_insert(value);
+            }
+        }
+    }
+}
+
+/// Error answered as `["error", code, message]` instead of a response object
+/// to the request with id `expected`, which the array itself does not carry
+pub(crate) fn error_array(response: &Value, expected: Option<&RpcId>) -> Option<JsonRpcError> {
+
+    match response.as_array()?.as_slice() {
+        [Value::String(tag), code, Value::String(message)] if tag == "error" => Some(JsonRpcError::Rpc {
+            code: code.as_i64()?,
+            message: message.clone(),
+            data: None,
+            id: expected.cloned(),
+        }),
+        _ => None,
+    }
+}
+
+/// Verify response belongs to the request with id `expected`
+///
+/// Servers answer with `id: null` when the request could not be parsed
+/// or was invalid, such error responses are accepted for any request.
+pub(crate) fn check_id(response: &Value, expected: &RpcId) -> Result<(), JsonRpcError> {
+
+    let expected = expected.to_value();
+    let received = response.get("id").unwrap_or(&Value::Null);
+
+    if *received == expected {
+        return Ok(());
+    }
+
+    let is_error = response.get("error").is_some_and(|error| !error.is_null());
+
+    if received.is_null() && is_error {
+        return Ok(());
+    }
+
+    Err(JsonRpcError::IdMismatch {
+        expected,
+        received: received.clone(),
+    })
+}
+
+/// Build `JsonRpcError::Rpc` from JSONRPC error object
+pub(crate) fn rpc_error(error: Value, id: Option<RpcId>) -> JsonRpcError {
+
+    JsonRpcError::Rpc {
+        code: error["code"].as_i64().unwrap_or_default(),
+        message: error["message"].as_str().unwrap_or_default().to_owned(),
+        data: error.get("data").cloned(),
+        id,
+    }
+}
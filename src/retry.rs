@@ -0,0 +1,160 @@
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use async_std::task;
+
+use crate::JsonRpcError;
+
+/// Exponential backoff schedule for transient failures
+///
+/// Connection errors, timeouts and HTTP 429 / 502 / 503 / 504 are retried,
+/// JSONRPC error responses are returned immediately.
+/// A `Retry-After` header sent with 429 or 503 overrides the schedule,
+/// capped at `max_delay` like any other delay.
+///
+/// # Examples
+///
+/// ```
+/// let policy = jsonrpc_v2_client::RetryPolicy {
+///     max_attempts: 5,
+///     ..Default::default()
+/// };
+/// assert_eq!(policy.delay(0), std::time::Duration::from_millis(100));
+/// assert_eq!(policy.delay(1), std::time::Duration::from_millis(200));
+/// ```
+///
+/// With `jitter` every delay is randomized within `delay * (1 ± jitter)`,
+/// fixing `seed` makes the randomization reproducible:
+///
+/// ```
+/// let policy = jsonrpc_v2_client::RetryPolicy {
+///     jitter: 0.5,
+///     seed: Some(7),
+///     ..Default::default()
+/// };
+/// let delay = policy.delay(0);
+/// assert!(delay >= std::time::Duration::from_millis(50) && delay <= std::time::Duration::from_millis(150));
+/// assert_eq!(delay, policy.delay(0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Factor applied to the delay after every retry
+    pub multiplier: f64,
+    /// Upper bound for a single delay, before jitter is applied
+    pub max_delay: Duration,
+    /// Fraction in `0.0..=1.0` by which delays are randomly shortened or lengthened
+    pub jitter: f64,
+    /// Seed of the jitter randomization, `None` picks a random seed for every delay
+    pub seed: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(10),
+            jitter: 0.0,
+            seed: None,
+        }
+    }
+
+}
+
+impl RetryPolicy {
+
+    /// Backoff delay before retry number `retry`, counted from 0
+    ///
+    /// Computed in `f64` and clamped to `max_delay` before jitter,
+    /// so large retry numbers saturate instead of overflowing.
+    pub fn delay(&self, retry: u32) -> Duration {
+
+        let max_delay = self.max_delay.as_secs_f64();
+        let delay = (self.initial_delay.as_secs_f64() * self.multiplier.powf(f64::from(retry))).min(max_delay);
+
+        Duration::try_from_secs_f64(delay * self.jitter_factor(retry)).unwrap_or(self.max_delay)
+    }
+
+    /// Random factor in `1 ± jitter`, derived from `seed` and `retry`
+    fn jitter_factor(&self, retry: u32) -> f64 {
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+
+        if jitter == 0.0 {
+            return 1.0;
+        }
+
+        let seed = self
+            .seed
+            .unwrap_or_else(|| RandomState::new().build_hasher().finish());
+        let unit = splitmix64(seed.wrapping_add(u64::from(retry))) as f64 / u64::MAX as f64;
+
+        1.0 + jitter * (2.0 * unit - 1.0)
+    }
+
+}
+
+/// SplitMix64 step, good enough spread for jitter without a RNG dependency
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn is_retryable(error: &JsonRpcError) -> bool {
+    match error {
+        JsonRpcError::ConnectionError(_) | JsonRpcError::Timeout => true,
+        JsonRpcError::HttpStatus { code, .. } => matches!(code, 429 | 502 | 503 | 504),
+        _ => false,
+    }
+}
+
+/// Run `operation` until it succeeds, fails permanently or attempts run out,
+/// returning its value together with the number of attempts made
+pub(crate) async fn retry<T, F, Fut>(policy: &RetryPolicy, mut operation: F) -> Result<(T, u32), JsonRpcError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, JsonRpcError>>,
+{
+    let mut attempt = 1;
+
+    loop {
+
+        let error = match operation().await {
+            Ok(value) => return Ok((value, attempt)),
+            Err(error) => error,
+        };
+
+        if attempt >= policy.max_attempts || !is_retryable(&error) {
+            return Err(error);
+        }
+
+        let delay = match error {
+            JsonRpcError::HttpStatus {
+                code: 429 | 503,
+                retry_after: Some(retry_after),
+            } => retry_after.min(policy.max_delay),
+            _ => policy.delay(attempt - 1),
+        };
+
+        log::warn!(
+            target: "jsonrpc_v2_client",
+            "[jsonrpc_v2_client: attempt {} failed, retrying in {:?}]: {}",
+            attempt,
+            delay,
+            error
+        );
+
+        task::sleep(delay).await;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,113 @@
+//! Request encoding and response decoding without any networking
+//!
+//! For callers running their own IO, e.g. a custom event loop: encode the
+//! request, write the bytes, and feed everything received so far to
+//! `decode_response` until it stops asking for more data.
+//!
+//! # Examples
+//!
+//! ```
+//! use jsonrpc_v2_client::sansio::{decode_response, encode_request, DecodeError};
+//! use jsonrpc_v2_client::{Params, Request, ServiceAddress};
+//!
+//! let service_address = ServiceAddress::new("127.0.0.1:8082", "/api");
+//! let request = Request::new("add", Params([1, 2]), "0");
+//! let bytes = encode_request(&request, &service_address, &[]).unwrap();
+//! assert!(bytes.starts_with(b"POST /api HTTP/1.1\r\n"));
+//!
+//! let received = b"HTTP/1.1 200 OK\r\nContent-Length: 37\r\n\r\n{\"jsonrpc\":\"2.0\",\"result\":3,\"id\":\"0\"}";
+//! assert!(matches!(decode_response(&received[..20]), Err(DecodeError::NeedMoreData)));
+//! let frame = decode_response(received).unwrap();
+//! assert_eq!(frame.status, 200);
+//! assert_eq!(frame.consumed, received.len());
+//! ```
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::http::{self, Frame};
+use crate::transport;
+use crate::{ClientConfig, JsonRpcError, Request, ResponseHeaders, ServiceAddress};
+
+/// Complete HTTP response decoded from received bytes
+#[derive(Clone, Debug)]
+pub struct ResponseFrame {
+    pub status: u16,
+    pub headers: ResponseHeaders,
+    /// Body with transfer and content codings removed
+    pub body: Vec<u8>,
+    /// Bytes taken up by the response, any further bytes belong to the next one
+    pub consumed: usize,
+    /// Connection may carry another request after this response
+    pub keep_alive: bool,
+}
+
+/// Reason `decode_response` returned no response
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// Bytes so far are a valid but incomplete response
+    NeedMoreData,
+    /// Bytes can never become a valid response
+    Invalid(JsonRpcError),
+}
+
+impl fmt::Display for DecodeError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NeedMoreData => write!(f, "incomplete HTTP response"),
+            DecodeError::Invalid(error) => write!(f, "{}", error),
+        }
+    }
+
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Frame `request` as HTTP POST to `service_address` with extra `headers`,
+/// using the default `ClientConfig`
+///
+/// The bytes are those `Request::send` writes, body serialization included.
+pub fn encode_request<T: Serialize>(
+    request: &Request<T>,
+    service_address: &ServiceAddress,
+    headers: &[(&str, &str)],
+) -> Result<Vec<u8>, JsonRpcError> {
+
+    transport::encode(request, service_address, None, &ClientConfig::default(), headers)
+}
+
+/// Decode the response at the start of `received`
+///
+/// Fails with `NeedMoreData` until the whole response arrived. Responses
+/// delimited only by the connection closing never complete here, pass them
+/// to `decode_closed_response` once the connection closed.
+pub fn decode_response(received: &[u8]) -> Result<ResponseFrame, DecodeError> {
+
+    match http::frame_response(received).map_err(DecodeError::Invalid)? {
+        Some(frame) => into_response_frame(frame, received).map_err(DecodeError::Invalid),
+        None => Err(DecodeError::NeedMoreData),
+    }
+}
+
+/// Decode `received` after the connection closed, the body is whatever
+/// arrived unless the response says its length
+pub fn decode_closed_response(received: &[u8]) -> Result<ResponseFrame, JsonRpcError> {
+
+    into_response_frame(http::closed_frame(received)?, received)
+}
+
+fn into_response_frame(frame: Frame, received: &[u8]) -> Result<ResponseFrame, JsonRpcError> {
+
+    let consumed = frame.end;
+    let response = frame.into_response(received[..consumed].to_vec())?;
+
+    Ok(ResponseFrame {
+        status: response.status,
+        headers: response.headers,
+        body: response.body,
+        consumed,
+        keep_alive: response.keep_alive,
+    })
+}
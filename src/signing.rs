@@ -0,0 +1,24 @@
+/// Source of signature headers added to every request sent by `Client`
+///
+/// `sign` receives the serialized request body and the current Unix time
+/// in seconds and returns the headers to send along.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpc_v2_client::RequestSigner;
+///
+/// struct BodyLength;
+///
+/// impl RequestSigner for BodyLength {
+///     fn sign(&self, body: &[u8], timestamp: u64) -> Vec<(String, String)> {
+///         vec![("X-Signed".to_owned(), format!("{}:{}", timestamp, body.len()))]
+///     }
+/// }
+///
+/// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+/// let client = jsonrpc_v2_client::Client::new(service_address).with_signer(BodyLength);
+/// ```
+pub trait RequestSigner: Send + Sync {
+    fn sign(&self, body: &[u8], timestamp: u64) -> Vec<(String, String)>;
+}
@@ -0,0 +1,129 @@
+use std::net::IpAddr;
+
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+
+use crate::JsonRpcError;
+
+const VERSION: u8 = 5;
+const NO_AUTHENTICATION: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+const CONNECT: u8 = 0x01;
+const IPV4: u8 = 0x01;
+const DOMAIN_NAME: u8 = 0x03;
+const IPV6: u8 = 0x04;
+
+/// Ask SOCKS5 proxy on `stream` to connect to `target` (`host:port`)
+pub(crate) async fn socks5_connect(
+    stream: &mut TcpStream,
+    target: &str,
+    auth: Option<&(String, String)>,
+) -> Result<(), JsonRpcError> {
+
+    let (host, port) = split_target(target)?;
+
+    let method = if auth.is_some() { USERNAME_PASSWORD } else { NO_AUTHENTICATION };
+    send(stream, &[VERSION, 1, method]).await?;
+
+    let reply = receive::<2>(stream).await?;
+    match (reply[1], auth) {
+        (NO_AUTHENTICATION, _) => {},
+        (USERNAME_PASSWORD, Some((username, password))) => {
+            authenticate(stream, username, password).await?;
+        },
+        (NO_ACCEPTABLE_METHODS, _) => return Err(socks_error("no acceptable authentication method")),
+        (other, _) => return Err(socks_error(&format!("unexpected authentication method {}", other))),
+    }
+
+    let mut request = vec![VERSION, CONNECT, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(IPV4);
+            request.extend_from_slice(&ip.octets());
+        },
+        Ok(IpAddr::V6(ip)) => {
+            request.push(IPV6);
+            request.extend_from_slice(&ip.octets());
+        },
+        Err(_) => {
+            let length = u8::try_from(host.len()).map_err(|_| socks_error("host name too long"))?;
+            request.push(DOMAIN_NAME);
+            request.push(length);
+            request.extend_from_slice(host.as_bytes());
+        },
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    send(stream, &request).await?;
+
+    let reply = receive::<4>(stream).await?;
+    if reply[1] != 0 {
+        return Err(socks_error(&format!("connect failed with reply {}", reply[1])));
+    }
+
+    // skip bound address and port
+    let address_length = match reply[3] {
+        IPV4 => 4,
+        IPV6 => 16,
+        DOMAIN_NAME => receive::<1>(stream).await?[0] as usize,
+        other => return Err(socks_error(&format!("unknown address type {}", other))),
+    };
+    let mut bound = vec![0u8; address_length + 2];
+    read_exact(stream, &mut bound).await
+}
+
+/// Username / password subnegotiation, RFC 1929
+async fn authenticate(stream: &mut TcpStream, username: &str, password: &str) -> Result<(), JsonRpcError> {
+
+    let username_length = u8::try_from(username.len()).map_err(|_| socks_error("username too long"))?;
+    let password_length = u8::try_from(password.len()).map_err(|_| socks_error("password too long"))?;
+
+    let mut request = vec![1, username_length];
+    request.extend_from_slice(username.as_bytes());
+    request.push(password_length);
+    request.extend_from_slice(password.as_bytes());
+    send(stream, &request).await?;
+
+    if receive::<2>(stream).await?[1] != 0 {
+        return Err(socks_error("authentication rejected"));
+    }
+
+    Ok(())
+}
+
+/// Split `host:port`, removing brackets around IPv6 literals
+fn split_target(target: &str) -> Result<(&str, u16), JsonRpcError> {
+
+    let (host, port) = target
+        .rsplit_once(':')
+        .ok_or_else(|| socks_error(&format!("no port in {}", target)))?;
+    let port = port
+        .parse::<u16>()
+        .map_err(|_| socks_error(&format!("invalid port in {}", target)))?;
+
+    Ok((host.trim_start_matches('[').trim_end_matches(']'), port))
+}
+
+async fn send(stream: &mut TcpStream, bytes: &[u8]) -> Result<(), JsonRpcError> {
+    stream
+        .write_all(bytes)
+        .await
+        .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))
+}
+
+async fn receive<const N: usize>(stream: &mut TcpStream) -> Result<[u8; N], JsonRpcError> {
+    let mut buffer = [0u8; N];
+    read_exact(stream, &mut buffer).await?;
+    Ok(buffer)
+}
+
+async fn read_exact(stream: &mut TcpStream, buffer: &mut [u8]) -> Result<(), JsonRpcError> {
+    stream
+        .read_exact(buffer)
+        .await
+        .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))
+}
+
+fn socks_error(message: &str) -> JsonRpcError {
+    JsonRpcError::ConnectionError(format!("SOCKS5: {}", message))
+}
@@ -0,0 +1,64 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+#[cfg(feature = "blocking")]
+use std::time::Duration;
+use std::time::Instant;
+
+use async_std::io::Read;
+
+/// Durations of request phases, each measured from the start of the send
+///
+/// `connect` ≤ `write` ≤ `first_byte` ≤ `total`, so `first_byte - write`
+/// approximates server latency and `connect` the network setup.
+///
+/// # Examples
+///
+/// ``` no_run
+/// let service_address = jsonrpc_v2_client::ServiceAddress::new("127.0.0.1:8082", "/api");
+/// let request = jsonrpc_v2_client::Request::new("add", jsonrpc_v2_client::Params([10.5, 20.5]), "0");
+/// let (response, timings) = request.send_timed(&service_address, None).unwrap();
+/// println!("{} in {:?}, server took {:?}", response["result"], timings.total, timings.first_byte - timings.write);
+/// ```
+#[cfg(feature = "blocking")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Connection established
+    pub connect: Duration,
+    /// Request fully written
+    pub write: Duration,
+    /// First byte of the response received
+    pub first_byte: Duration,
+    /// Response fully received
+    pub total: Duration,
+}
+
+/// Reader noting when the first byte arrives
+pub(crate) struct FirstByte<'a, S> {
+    inner: &'a mut S,
+    pub received: Option<Instant>,
+}
+
+impl<'a, S> FirstByte<'a, S> {
+
+    pub fn new(inner: &'a mut S) -> FirstByte<'a, S> {
+        FirstByte { inner, received: None }
+    }
+
+}
+
+impl<S: Read + Unpin> Read for FirstByte<'_, S> {
+
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+
+        let poll = Pin::new(&mut *self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(size)) = poll {
+            if size > 0 && self.received.is_none() {
+                self.received = Some(Instant::now());
+            }
+        }
+
+        poll
+    }
+
+}
@@ -0,0 +1,477 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+#[cfg(feature = "blocking")]
+use std::time::Instant;
+
+use async_std::io::{BufWriter, Read, Write};
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+use serde::Serialize;
+
+#[cfg(feature = "blocking")]
+use crate::dns;
+use crate::http::{self, HttpResponse};
+use crate::timing::FirstByte;
+#[cfg(feature = "blocking")]
+use crate::Timings;
+use crate::{APIKey, ClientConfig, JsonRpcError, Scheme, ServiceAddress};
+
+/// Send serialized `payload` and return parsed response,
+/// reporting every failure as `JsonRpcError` instead of panicking
+#[cfg(feature = "blocking")]
+pub(crate) async fn exchange<B: Serialize + ?Sized>(
+    payload: &B,
+    service_address: &ServiceAddress,
+    api_key: Option<&APIKey>,
+    config: &ClientConfig,
+) -> Result<serde_json::Value, JsonRpcError> {
+
+    let response = fetch(payload, service_address, api_key, config).await?;
+
+    parse_body(&response)
+}
+
+/// Send serialized `payload` and return the HTTP response, honoring `config.timeout`
+#[cfg(feature = "blocking")]
+pub(crate) async fn fetch<B: Serialize + ?Sized>(
+    payload: &B,
+    service_address: &ServiceAddress,
+    api_key: Option<&APIKey>,
+    config: &ClientConfig,
+) -> Result<HttpResponse, JsonRpcError> {
+
+    let request = encode(payload, service_address, api_key, config, &[])?;
+
+    limited(config.timeout, async {
+        let mut client = connect(service_address).await?;
+        roundtrip(&mut client, &request, config, Vec::new()).await
+    })
+    .await
+}
+
+/// Run `operation`, failing with `JsonRpcError::Timeout` once `timeout` elapsed
+#[cfg(feature = "blocking")]
+pub(crate) async fn limited<T, F>(timeout: Option<Duration>, operation: F) -> Result<T, JsonRpcError>
+where
+    F: Future<Output = Result<T, JsonRpcError>>,
+{
+    match timeout {
+        Some(timeout) => async_std::future::timeout(timeout, operation)
+            .await
+            .map_err(|_| JsonRpcError::Timeout)?,
+        None => operation.await,
+    }
+}
+
+/// Like `exchange`, additionally measuring how long each phase took
+#[cfg(feature = "blocking")]
+pub(crate) async fn timed_exchange<B: Serialize + ?Sized>(
+    payload: &B,
+    service_address: &ServiceAddress,
+    api_key: Option<&APIKey>,
+    config: &ClientConfig,
+) -> Result<(serde_json::Value, Timings), JsonRpcError> {
+
+    let request = encode(payload, service_address, api_key, config, &[])?;
+
+    limited(config.timeout, async {
+        let start = Instant::now();
+        let mut client = connect(service_address).await?;
+        let connect = start.elapsed();
+
+        write_buffered(&mut client, &request, config.write_buffer_size)
+            .await
+            .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?;
+        let write = start.elapsed();
+
+        let mut reader = FirstByte::new(&mut client);
+        let response = http::read_response(&mut reader, Vec::new(), config.read_idle_timeout).await?;
+        let total = start.elapsed();
+        let first_byte = reader.received.map_or(total, |received| received - start);
+
+        let timings = Timings {
+            connect,
+            write,
+            first_byte,
+            total,
+        };
+
+        Ok((parse_body(&response)?, timings))
+    })
+    .await
+}
+
+/// Serialize `payload` and frame it as HTTP request with extra `headers`
+pub(crate) fn encode<B: Serialize + ?Sized>(
+    payload: &B,
+    service_address: &ServiceAddress,
+    api_key: Option<&APIKey>,
+    config: &ClientConfig,
+    headers: &[(&str, &str)],
+) -> Result<Vec<u8>, JsonRpcError> {
+
+    let json = serde_json::to_vec_pretty(payload)
+        .map_err(|error| JsonRpcError::SerializationError(error.to_string()))?;
+
+    Ok(frame(&json, service_address, api_key, config, headers))
+}
+
+/// Frame already serialized JSON `body` as HTTP request with extra `headers`
+pub(crate) fn frame(
+    body: &[u8],
+    service_address: &ServiceAddress,
+    api_key: Option<&APIKey>,
+    config: &ClientConfig,
+    headers: &[(&str, &str)],
+) -> Vec<u8> {
+
+    let request = http::encode_request(service_address, api_key, config, headers, body);
+
+    log::trace!(
+        target: "jsonrpc_v2_client",
+        "[jsonrpc_v2_client: request as string]\r\n{}",
+        String::from_utf8_lossy(&request)
+    );
+
+    request
+}
+
+/// Open new connection to the service, failing with `ResolutionError`
+/// when its host does not resolve
+#[cfg(feature = "blocking")]
+pub(crate) async fn connect(service_address: &ServiceAddress) -> Result<TcpStream, JsonRpcError> {
+
+    check_scheme(service_address)?;
+
+    let addresses = dns::lookup(&service_address.url).await?;
+
+    connect_any(&addresses).await
+}
+
+/// Fail for schemes the client cannot speak, there is no TLS support
+pub(crate) fn check_scheme(service_address: &ServiceAddress) -> Result<(), JsonRpcError> {
+
+    match service_address.scheme {
+        Scheme::Http => Ok(()),
+        scheme => Err(JsonRpcError::ConnectionError(format!(
+            "{} is not supported, only plain HTTP",
+            scheme.as_str()
+        ))),
+    }
+}
+
+/// Connect to the first reachable of `addresses`
+pub(crate) async fn connect_any(addresses: &[SocketAddr]) -> Result<TcpStream, JsonRpcError> {
+
+    let mut last_error = None;
+
+    for address in addresses {
+        match TcpStream::connect(address).await {
+            Ok(stream) => {
+                log::debug!(
+                    target: "jsonrpc_v2_client",
+                    "[jsonrpc_v2_client: connected to {}]",
+                    address
+                );
+                return Ok(stream);
+            }
+            Err(error) => {
+                log::debug!(
+                    target: "jsonrpc_v2_client",
+                    "[jsonrpc_v2_client: connecting to {} failed]: {}",
+                    address,
+                    error
+                );
+                last_error = Some(error);
+            }
+        }
+    }
+
+    Err(JsonRpcError::ConnectionError(match last_error {
+        Some(error) => error.to_string(),
+        None => "no addresses to connect to".to_owned(),
+    }))
+}
+
+/// Apply socket options of `config` to new connection `stream`
+pub(crate) fn configure(stream: &TcpStream, config: &ClientConfig) -> Result<(), JsonRpcError> {
+
+    stream
+        .set_nodelay(config.tcp_nodelay)
+        .map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?;
+
+    if let Some(idle) = config.tcp_keepalive {
+        set_keepalive(stream, idle).map_err(|error| JsonRpcError::ConnectionError(error.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Enable keepalive probes once `stream` was idle for `idle`
+#[cfg(unix)]
+fn set_keepalive(stream: &TcpStream, idle: Duration) -> std::io::Result<()> {
+
+    use std::os::unix::io::AsRawFd;
+
+    let fd = stream.as_raw_fd();
+    setsockopt(fd, libc::SOL_SOCKET, libc::SO_KEEPALIVE, 1)?;
+
+    let seconds = libc::c_int::try_from(idle.as_secs().max(1)).unwrap_or(libc::c_int::MAX);
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd"))]
+    setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPIDLE, seconds)?;
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPALIVE, seconds)?;
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "macos",
+        target_os = "ios"
+    )))]
+    let _ = seconds;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_keepalive(_: &TcpStream, _: Duration) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "tcp_keepalive is only supported on unix",
+    ))
+}
+
+#[cfg(unix)]
+fn setsockopt(fd: libc::c_int, level: libc::c_int, name: libc::c_int, value: libc::c_int) -> std::io::Result<()> {
+
+    // SAFETY: `value` outlives the call, which reads exactly its size
+    let result = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Write `request` through buffer of `capacity` bytes, flushing once at the end
+pub(crate) async fn write_buffered<W: Write + Unpin>(writer: &mut W, request: &[u8], capacity: usize) -> std::io::Result<()> {
+
+    let mut writer = BufWriter::with_capacity(capacity, writer);
+
+    writer.write_all(request).await?;
+    writer.flush().await
+}
+
+/// Write framed `request` to `client` and read the HTTP response into `buffer`
+pub(crate) async fn roundtrip<S>(
+    client: &mut S,
+    request: &[u8],
+    config: &ClientConfig,
+    buffer: Vec<u8>,
+) -> Result<HttpResponse, JsonRpcError>
+where
+    S: Read + Write + Unpin,
+{
+
+    send(client, request, config).await?;
+    receive(client, buffer, config).await
+}
+
+/// Failure of `roundtrip_reused`
+pub(crate) enum ReuseError {
+    /// Connection was found closed before any response byte arrived,
+    /// as when the server dropped it while idle, so sending again is safe
+    Stale(JsonRpcError),
+    /// Server may have received the request, it must not be sent again
+    Failed(JsonRpcError),
+}
+
+/// Like `roundtrip` over a pooled keep-alive connection, telling a stale
+/// connection apart from failures of a request the server may be answering
+pub(crate) async fn roundtrip_reused<S>(
+    client: &mut S,
+    request: &[u8],
+    config: &ClientConfig,
+    buffer: Vec<u8>,
+) -> Result<HttpResponse, ReuseError>
+where
+    S: Read + Write + Unpin,
+{
+
+    send(client, request, config).await.map_err(ReuseError::Stale)?;
+
+    let mut reader = FirstByte::new(client);
+    match receive(&mut reader, buffer, config).await {
+        Ok(response) => Ok(response),
+        // the server accepted the request and is slow to answer it
+        Err(JsonRpcError::Timeout) => Err(ReuseError::Failed(JsonRpcError::Timeout)),
+        Err(error) if reader.received.is_none() => Err(ReuseError::Stale(error)),
+        Err(error) => Err(ReuseError::Failed(error)),
+    }
+}
+
+/// Write framed `request` to `client`
+async fn send<S: Write + Unpin>(client: &mut S, request: &[u8], config: &ClientConfig) -> Result<(), JsonRpcError> {
+
+    log::trace!(
+        target: "jsonrpc_v2_client",
+        "[jsonrpc_v2_client: sending request]"
+    );
+
+    // send request to the server
+    match write_buffered(client, request, config.write_buffer_size).await {
+
+        Ok(_) => {
+            log::info!(
+                target: "jsonrpc_v2_client",
+                "[jsonrpc_v2_client: request successfully sent]"
+            );
+        },
+        Err(error) => {
+            log::error!(
+                target: "jsonrpc_v2_client",
+                "[jsonrpc_v2_client: error]: {}",
+                error
+            );
+            return Err(JsonRpcError::ConnectionError(error.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the HTTP response from `client` into `buffer`
+async fn receive<S: Read + Unpin>(client: &mut S, buffer: Vec<u8>, config: &ClientConfig) -> Result<HttpResponse, JsonRpcError> {
+
+    log::trace!(
+        target: "jsonrpc_v2_client",
+        "[jsonrpc_v2_client: reading response]"
+    );
+
+    // read the response
+    let response = http::read_response(client, buffer, config.read_idle_timeout).await?;
+
+    log::trace!(
+        target: "jsonrpc_v2_client",
+        "[jsonrpc_v2_client: response status = {}]",
+        response.status
+    );
+
+    Ok(response)
+}
+
+/// Parse response body as JSON, mapping plain HTTP errors to `HttpStatus`
+pub(crate) fn parse_body(response: &HttpResponse) -> Result<serde_json::Value, JsonRpcError> {
+
+    let json = serde_json::from_slice::<serde_json::Value>(&response.body);
+
+    match json {
+        Ok(value) if value.is_object() || value.is_array() => Ok(value),
+        // servers may answer with a plain HTTP error, e.g. when rate limiting
+        _ if !(200..300).contains(&response.status) => Err(JsonRpcError::HttpStatus {
+            code: response.status,
+            retry_after: response.retry_after(),
+        }),
+        Ok(value) => Ok(value),
+        Err(error) => Err(JsonRpcError::response_error(error.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Writer recording written bytes and `flush` calls
+    #[derive(Default)]
+    struct Recorder {
+        written: Vec<u8>,
+        writes: usize,
+        flushes: usize,
+    }
+
+    impl async_std::io::Write for Recorder {
+
+        fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+            self.written.extend_from_slice(buf);
+            self.writes += 1;
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            self.flushes += 1;
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_configure_sets_keepalive() {
+        use std::os::unix::io::AsRawFd;
+        use std::time::Duration;
+
+        use crate::ClientConfig;
+
+        fn getsockopt(fd: libc::c_int, level: libc::c_int, name: libc::c_int) -> libc::c_int {
+            let mut value: libc::c_int = 0;
+            let mut length = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+            let result = unsafe {
+                libc::getsockopt(fd, level, name, &mut value as *mut libc::c_int as *mut libc::c_void, &mut length)
+            };
+            assert_eq!(result, 0);
+            value
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let stream = async_std::task::block_on(async_std::net::TcpStream::connect(address)).unwrap();
+
+        let config = ClientConfig {
+            tcp_nodelay: true,
+            tcp_keepalive: Some(Duration::from_secs(45)),
+            ..Default::default()
+        };
+        super::configure(&stream, &config).unwrap();
+
+        let fd = stream.as_raw_fd();
+        assert_ne!(getsockopt(fd, libc::SOL_SOCKET, libc::SO_KEEPALIVE), 0);
+        #[cfg(target_os = "linux")]
+        assert_eq!(getsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPIDLE), 45);
+        assert!(stream.nodelay().unwrap());
+    }
+
+    #[test]
+    fn test_write_buffered_flushes_once() {
+        let request: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+        for capacity in [1024, 64 * 1024, 8 * 1024 * 1024] {
+            let mut recorder = Recorder::default();
+            async_std::task::block_on(super::write_buffered(&mut recorder, &request, capacity)).unwrap();
+
+            assert_eq!(recorder.written, request);
+            assert_eq!(recorder.flushes, 1);
+        }
+
+        let mut recorder = Recorder::default();
+        async_std::task::block_on(super::write_buffered(&mut recorder, b"small", 1024)).unwrap();
+        assert_eq!(recorder.writes, 1);
+    }
+
+}
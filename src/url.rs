@@ -0,0 +1,97 @@
+use base64::Engine;
+
+use crate::{JsonRpcError, Scheme};
+
+/// Components of `http[s]://[user[:password]@]host[:port][/path]`
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParsedUrl {
+    pub scheme: Scheme,
+    /// `host:port`, port defaults to 80 or 443 depending on `scheme`
+    pub authority: String,
+    /// Request path, defaults to `/`
+    pub path: String,
+    /// `Authorization` header value built from userinfo
+    pub authorization: Option<String>,
+}
+
+pub(crate) fn parse(url: &str) -> Result<ParsedUrl, JsonRpcError> {
+
+    let (scheme, rest) = Scheme::split(url)
+        .ok_or_else(|| JsonRpcError::InvalidUrl(format!("unsupported scheme in {}", url)))?;
+
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(index) if rest[index..].starts_with('/') => (&rest[..index], rest[index..].to_owned()),
+        Some(index) => (&rest[..index], format!("/{}", &rest[index..])),
+        None => (rest, "/".to_owned()),
+    };
+
+    let (userinfo, host) = match authority.rsplit_once('@') {
+        Some((userinfo, host)) => (Some(userinfo), host),
+        None => (None, authority),
+    };
+
+    if host.is_empty() {
+        return Err(JsonRpcError::InvalidUrl(format!("missing host in {}", url)));
+    }
+
+    let authority = with_default_port(host, scheme);
+
+    let authorization = match userinfo {
+        Some(userinfo) => {
+            let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+            let credentials = format!("{}:{}", percent_decode(user)?, percent_decode(password)?);
+            Some(format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            ))
+        },
+        None => None,
+    };
+
+    Ok(ParsedUrl {
+        scheme,
+        authority,
+        path,
+        authorization,
+    })
+}
+
+/// `host` with the port of `scheme` appended unless it names one
+pub(crate) fn with_default_port(host: &str, scheme: Scheme) -> String {
+
+    // `[::1]` alone has no port, `[::1]:8080` and `host:8080` do
+    let has_port = match host.rfind(']') {
+        Some(bracket) => host[bracket..].contains(':'),
+        None => host.contains(':'),
+    };
+
+    if has_port {
+        host.to_owned()
+    } else {
+        format!("{}:{}", host, scheme.default_port())
+    }
+}
+
+/// Decode `%XX` escapes used in URL userinfo
+fn percent_decode(value: &str) -> Result<String, JsonRpcError> {
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let byte = value
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| JsonRpcError::InvalidUrl(format!("invalid escape in {}", value)))?;
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|error| JsonRpcError::InvalidUrl(error.to_string()))
+}
@@ -1,6 +1,200 @@
 #[cfg(test)]
 mod tests {
 
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::thread;
+
+    /// Read one HTTP request (head and `Content-Length` body) from `stream`
+    fn read_http_request(stream: &mut std::net::TcpStream) -> String {
+        String::from_utf8_lossy(&read_http_request_bytes(stream)).into_owned()
+    }
+
+    /// Like `read_http_request`, keeping a binary body intact
+    fn read_http_request_bytes(stream: &mut std::net::TcpStream) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 1024];
+
+        loop {
+            if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                let length = String::from_utf8_lossy(&buffer[..end])
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: ").map(str::to_owned))
+                    .map(|value| value.trim().parse::<usize>().unwrap())
+                    .unwrap_or(0);
+                if buffer.len() >= end + 4 + length {
+                    return buffer;
+                }
+            }
+            let size = stream.read(&mut chunk).unwrap();
+            if size == 0 {
+                return buffer;
+            }
+            buffer.extend_from_slice(&chunk[..size]);
+        }
+    }
+
+    /// Serve `connections` connections on an ephemeral port, answering each
+    /// request with the JSON body built by `handler` from the request body
+    ///
+    /// The join handle yields the raw requests received.
+    fn serve<F>(
+        connections: usize,
+        handler: F,
+    ) -> (jsonrpc_v2_client::ServiceAddress, thread::JoinHandle<Vec<String>>)
+    where
+        F: Fn(&serde_json::Value) -> String + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for _ in 0..connections {
+                let (mut stream, _) = listener.accept().unwrap();
+                let request = read_http_request(&mut stream);
+                let body = request.split("\r\n\r\n").nth(1).unwrap_or_default();
+                let json = handler(&serde_json::from_str(body).unwrap());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    json.len(),
+                    json
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        (jsonrpc_v2_client::ServiceAddress::new(&url, "/api"), handle)
+    }
+
+    /// Serve any number of keep-alive connections, answering every request
+    /// with the JSON body built by `handler`
+    ///
+    /// The counter tracks accepted connections.
+    fn serve_keep_alive<F>(
+        handler: F,
+    ) -> (jsonrpc_v2_client::ServiceAddress, std::sync::Arc<std::sync::atomic::AtomicUsize>)
+    where
+        F: Fn(&serde_json::Value) -> String + Send + Sync + 'static,
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let handler = Arc::new(handler);
+
+        let counter = accepted.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let handler = handler.clone();
+                counter.fetch_add(1, Ordering::SeqCst);
+                thread::spawn(move || loop {
+                    let request = read_http_request(&mut stream);
+                    let body = match request.split_once("\r\n\r\n") {
+                        Some((_, body)) => body.to_owned(),
+                        None => return,
+                    };
+                    let json = handler(&serde_json::from_str(&body).unwrap());
+                    if stream.write_all(http_ok(&json).as_bytes()).is_err() {
+                        return;
+                    }
+                });
+            }
+        });
+
+        (jsonrpc_v2_client::ServiceAddress::new(&url, "/api"), accepted)
+    }
+
+    /// Answer connection `n` with raw HTTP response `responses[n]`
+    ///
+    /// The join handle yields the raw requests received.
+    fn serve_raw(responses: Vec<String>) -> (jsonrpc_v2_client::ServiceAddress, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_http_request(&mut stream));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        (jsonrpc_v2_client::ServiceAddress::new(&url, "/api"), handle)
+    }
+
+    /// Wrap JSON `body` into HTTP 200 response
+    fn http_ok(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    /// Install logger collecting lines of this crate, shared by all tests
+    /// since a process has only one logger
+    fn capture_logs() -> &'static Mutex<Vec<String>> {
+        use std::sync::Once;
+
+        static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        static INSTALL: Once = Once::new();
+
+        struct Capture;
+
+        impl log::Log for Capture {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == "jsonrpc_v2_client"
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    LINES.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        INSTALL.call_once(|| {
+            log::set_logger(&Capture).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+
+        &LINES
+    }
+
+    /// Mock server multiplying exactly two positional params
+    fn mul_server() -> jsonrpc_v2_client::RunningMockRpcServer {
+        jsonrpc_v2_client::MockRpcServer::new()
+            .handler("mul", |params| match params.as_array() {
+                Some(factors) if factors.len() == 2 => {
+                    Ok(serde_json::json!(factors[0].as_f64().unwrap() * factors[1].as_f64().unwrap()))
+                },
+                _ => Err(serde_json::json!({"code": -32602, "message": "Invalid params"})),
+            })
+            .start()
+    }
+
+    /// Answer `mul` requests with the product of positional params
+    fn mul_handler(request: &serde_json::Value) -> String {
+        let product: f64 = request["params"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_f64().unwrap())
+            .product();
+        serde_json::json!({"jsonrpc": "2.0", "result": product, "id": request["id"]}).to_string()
+    }
+
     #[test]
     fn test_params() {
         use jsonrpc_v2_client::Params;
@@ -23,17 +217,17 @@ mod tests {
         assert_eq!(api_key.as_header(), "API_KEY: abcdef12345678");
     }
 
+    #[cfg(feature = "blocking")]
     #[test]
     fn test_request_with_api_key() {
         use jsonrpc_v2_client::APIKey;
         use jsonrpc_v2_client::Params;
         use jsonrpc_v2_client::Request;
-        use jsonrpc_v2_client::ServiceAddress;
         use serde_json::Value::Null;
 
-
         let api_key = APIKey::new("X-API-KEY", "abcdef12345678");
-        let service_address = ServiceAddress::new("127.0.0.1:8082", "/api");
+        let server = mul_server();
+        let service_address = server.service_address();
         let req = Request::new("mul", Params([2.5, 3.5]), "0");
 
         let response = req.send(&service_address, Some(&api_key));
@@ -42,16 +236,18 @@ mod tests {
         assert_eq!(response["result"], 8.75);
         assert_eq!(response["id"], "0");
         assert_eq!(response["error"], Null);
+        assert!(server.requests()[0].contains("\r\nX-API-KEY: abcdef12345678\r\n"));
     }
 
+    #[cfg(feature = "blocking")]
     #[test]
     fn test_request_without_api_key() {
         use jsonrpc_v2_client::Params;
         use jsonrpc_v2_client::Request;
-        use jsonrpc_v2_client::ServiceAddress;
         use serde_json::Value::Null;
 
-        let service_address = ServiceAddress::new("127.0.0.1:8082", "/api");
+        let server = mul_server();
+        let service_address = server.service_address();
         let req = Request::new("mul", Params([2.5, 3.5]), "0");
 
         let response = req.send(&service_address, None);
@@ -62,14 +258,15 @@ mod tests {
         assert_eq!(response["error"], Null);
     }
 
+    #[cfg(feature = "blocking")]
     #[test]
     fn test_request_error_response() {
         use jsonrpc_v2_client::Params;
         use jsonrpc_v2_client::Request;
-        use jsonrpc_v2_client::ServiceAddress;
         use serde_json::Value::Null;
 
-        let service_address = ServiceAddress::new("127.0.0.1:8082", "/api");
+        let server = mul_server();
+        let service_address = server.service_address();
         // error produced here, this method takes only 2 params
         let req = Request::new("mul", Params([2.5, 3.5, 3.0]), "0");
 
@@ -81,4 +278,2890 @@ mod tests {
         assert_eq!(response["error"]["code"], -32602);
         assert_eq!(response["error"]["message"], "Invalid params");
     }
+
+    #[test]
+    fn test_client_invoke() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::RpcMethod;
+
+        struct Mul;
+
+        impl RpcMethod for Mul {
+            const NAME: &'static str = "mul";
+            type Params = [f64; 2];
+            type Output = f64;
+        }
+
+        let (service_address, server) = serve(1, mul_handler);
+        let client = Client::new(service_address);
+
+        let product = async_std::task::block_on(client.invoke::<Mul>([2.5, 3.5])).unwrap();
+        assert_eq!(product, 8.75);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("\"method\": \"mul\""));
+    }
+
+    #[test]
+    fn test_client_id_mismatch() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, _) = serve(1, |_| {
+            r#"{"jsonrpc": "2.0", "result": 1, "id": "unrelated"}"#.to_owned()
+        });
+        let client = Client::new(service_address);
+
+        let error = async_std::task::block_on(client.call::<_, f64>("mul", Params([2.5, 3.5])))
+            .unwrap_err();
+        assert!(matches!(error, JsonRpcError::IdMismatch { .. }));
+    }
+
+    #[test]
+    fn test_client_parse_error_with_null_id() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, _) = serve(1, |_| {
+            r#"{"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}"#
+                .to_owned()
+        });
+        let client = Client::new(service_address);
+
+        let error = async_std::task::block_on(client.call::<_, f64>("mul", Params([2.5, 3.5])))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            JsonRpcError::Rpc {
+                code: -32700,
+                message: "Parse error".to_owned(),
+                data: None,
+                id: Some(jsonrpc_v2_client::RpcId::from("1")),
+            }
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_client_call() {
+        use jsonrpc_v2_client::BlockingClient;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, _) = serve(1, mul_handler);
+        let client = BlockingClient::new(service_address);
+
+        let product: f64 = client.call("mul", Params([2.5, 3.5]), "7").unwrap();
+        assert_eq!(product, 8.75);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_client_timeout() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::BlockingClient;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        // accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(2));
+            drop(stream);
+        });
+
+        let config = ClientConfig {
+            timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let client = BlockingClient::new(ServiceAddress::new(&url, "/api")).with_config(config);
+
+        let result = client.call::<_, f64>("mul", Params([2.5, 3.5]), "0");
+        assert_eq!(result, Err(JsonRpcError::Timeout));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_dyn_request() {
+        use jsonrpc_v2_client::DynRequest;
+        use jsonrpc_v2_client::RpcId;
+
+        let (service_address, _) = serve(2, mul_handler);
+
+        let factors: Vec<f64> = "2.5 3.5".split(' ').map(|x| x.parse().unwrap()).collect();
+        let calls = [
+            ("mul", serde_json::json!(factors), RpcId::from(1)),
+            ("mul", serde_json::json!([2, 3, 4]), RpcId::from("2")),
+        ];
+        let requests: Vec<DynRequest> = calls
+            .into_iter()
+            .map(|(method, params, id)| DynRequest::new(method, params, id))
+            .collect();
+
+        let response = requests[0].send(&service_address, None);
+        assert_eq!(response["result"], 8.75);
+        assert_eq!(response["id"], 1);
+
+        let response = requests[1].send(&service_address, None);
+        assert_eq!(response["result"], 24.0);
+        assert_eq!(response["id"], "2");
+        assert_eq!(requests[1].id, RpcId::from("2"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_with_retry_honors_retry_after() {
+        use std::time::{Duration, Instant};
+
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::RetryPolicy;
+
+        let (service_address, server) = serve_raw(vec![
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Length: 0\r\n\r\n".to_owned(),
+            http_ok(r#"{"jsonrpc": "2.0", "result": 8.75, "id": "0"}"#),
+        ]);
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let request = Request::new("mul", Params([2.5, 3.5]), "0");
+
+        let started = Instant::now();
+        let product: f64 = request.send_with_retry(&service_address, None, &policy).unwrap();
+        let elapsed = started.elapsed();
+
+        assert_eq!(product, 8.75);
+        assert!(elapsed >= Duration::from_millis(950), "retried after {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(3), "retried after {:?}", elapsed);
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_client_connection_hooks() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, accepted) = serve_keep_alive(mul_handler);
+        let connects = Arc::new(AtomicUsize::new(0));
+        let disconnects = Arc::new(AtomicUsize::new(0));
+
+        let connect_counter = connects.clone();
+        let disconnect_counter = disconnects.clone();
+        let client = Client::new(service_address)
+            .on_connect(move |peer| {
+                assert!(peer.ip().is_loopback());
+                connect_counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .on_disconnect(move |_| {
+                disconnect_counter.fetch_add(1, Ordering::SeqCst);
+            });
+
+        async_std::task::block_on(async {
+            for _ in 0..3 {
+                let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+                assert_eq!(product, 8.75);
+            }
+        });
+
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+        assert_eq!(disconnects.load(Ordering::SeqCst), 0);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_client_reconnects_closed_connection() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        // server closes every connection after one response
+        let (service_address, _) = serve(2, mul_handler);
+        let connects = Arc::new(AtomicUsize::new(0));
+
+        let connect_counter = connects.clone();
+        let client = Client::new(service_address).on_connect(move |_| {
+            connect_counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        async_std::task::block_on(async {
+            for _ in 0..2 {
+                let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+                assert_eq!(product, 8.75);
+            }
+        });
+
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_client_call_streaming() {
+        use jsonrpc_v2_client::Client;
+
+        let (service_address, server) = serve(1, |request| {
+            let count = request["params"].as_array().unwrap().len();
+            serde_json::json!({"jsonrpc": "2.0", "result": count, "id": request["id"]}).to_string()
+        });
+        let client = Client::new(service_address);
+
+        let count: usize = async_std::task::block_on(client.call_streaming("count", |writer| {
+            writer.write_all(b"[")?;
+            for i in 0..10_000 {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                write!(writer, "{}", i)?;
+            }
+            writer.write_all(b"]")
+        }))
+        .unwrap();
+        assert_eq!(count, 10_000);
+
+        let request = server.join().unwrap().remove(0);
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let content_length = format!("Content-Length: {}", body.len());
+        assert!(head.lines().any(|line| line == content_length));
+
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(json["method"], "count");
+        assert_eq!(json["params"][9_999], 9_999);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_service_address_from_url_with_credentials() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let (local_address, server) = serve(1, mul_handler);
+        let url = format!("http://user:p%40ss@{}/rpc", local_address.url);
+
+        let service_address = ServiceAddress::from_url(&url).unwrap();
+        assert_eq!(service_address.url, local_address.url);
+        assert_eq!(service_address.endpoint, "/rpc");
+        // base64 of "user:p@ss"
+        assert_eq!(service_address.authorization.as_deref(), Some("Basic dXNlcjpwQHNz"));
+
+        let response = Request::new("mul", Params([2.5, 3.5]), "0").send(&service_address, None);
+        assert_eq!(response["result"], 8.75);
+
+        let request = server.join().unwrap().remove(0);
+        assert!(request.starts_with("POST /rpc HTTP/1.1\r\n"));
+        assert!(request.contains(&format!("\r\nHost: {}\r\n", local_address.url)));
+        assert!(request.contains("\r\nAuthorization: Basic dXNlcjpwQHNz\r\n"));
+        assert!(!request.contains("p%40ss"));
+    }
+
+    #[test]
+    fn test_service_address_from_url_defaults() {
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let service_address = ServiceAddress::from_url("http://example.com").unwrap();
+        assert_eq!(service_address.url, "example.com:80");
+        assert_eq!(service_address.endpoint, "/");
+        assert_eq!(service_address.authorization, None);
+
+        assert!(matches!(
+            ServiceAddress::from_url("ftp://example.com"),
+            Err(JsonRpcError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_custom_id_generator() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::IdGenerator;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RpcId;
+
+        struct Fixed(Mutex<Vec<RpcId>>);
+
+        impl IdGenerator for Fixed {
+            fn next_id(&self) -> RpcId {
+                self.0.lock().unwrap().remove(0)
+            }
+        }
+
+        let (service_address, server) = serve(2, mul_handler);
+        let generator = Fixed(Mutex::new(vec![RpcId::from("fixed-a"), RpcId::from(42)]));
+        let client = Client::new(service_address).with_id_generator(generator);
+
+        async_std::task::block_on(async {
+            for _ in 0..2 {
+                let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+                assert_eq!(product, 8.75);
+            }
+        });
+
+        let ids: Vec<serde_json::Value> = server
+            .join()
+            .unwrap()
+            .iter()
+            .map(|request| {
+                let body = request.split_once("\r\n\r\n").unwrap().1;
+                serde_json::from_str::<serde_json::Value>(body).unwrap()["id"].clone()
+            })
+            .collect();
+        assert_eq!(ids, [serde_json::json!("fixed-a"), serde_json::json!(42)]);
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_id_generator() {
+        use jsonrpc_v2_client::IdGenerator;
+        use jsonrpc_v2_client::RpcId;
+        use jsonrpc_v2_client::UuidIdGenerator;
+
+        let generator = UuidIdGenerator;
+        let first = generator.next_id();
+        let second = generator.next_id();
+
+        assert_ne!(first, second);
+        match first {
+            RpcId::String(id) => assert_eq!(id.len(), 36),
+            other => panic!("unexpected id {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_with_meta_headers() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, _) = serve(1, mul_handler);
+        let request = Request::new("mul", Params([2.5, 3.5]), "0");
+
+        let (response, headers) = request.send_with_meta(&service_address, None).unwrap();
+
+        assert_eq!(response["result"], 8.75);
+        assert_eq!(headers.get("content-type"), Some("application/json"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("application/json"));
+        assert_eq!(headers.get("x-missing"), None);
+    }
+
+    #[cfg(not(any(feature = "gzip", feature = "deflate")))]
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_accept_encoding_absent_without_compression() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, server) = serve(1, mul_handler);
+        Request::new("mul", Params([2.5, 3.5]), "0").send(&service_address, None);
+
+        let request = server.join().unwrap().remove(0);
+        assert!(!request.to_ascii_lowercase().contains("accept-encoding"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_accept_encoding_and_gzip_response() {
+        use std::io::Write;
+
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(br#"{"jsonrpc": "2.0", "result": 8.75, "id": "0"}"#)
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            compressed.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(&compressed);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_address = jsonrpc_v2_client::ServiceAddress::new(
+            &listener.local_addr().unwrap().to_string(),
+            "/api",
+        );
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_http_request(&mut stream);
+            stream.write_all(&response).unwrap();
+            request
+        });
+
+        let response = Request::new("mul", Params([2.5, 3.5]), "0").send(&service_address, None);
+        assert_eq!(response["result"], 8.75);
+
+        let request = server.join().unwrap();
+        let accept_encoding = request
+            .lines()
+            .find_map(|line| line.strip_prefix("Accept-Encoding: "))
+            .unwrap();
+        assert!(accept_encoding.contains("gzip"));
+    }
+
+    #[test]
+    fn test_client_without_accept_header() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, server) = serve(2, mul_handler);
+        let config = ClientConfig {
+            accept_header: None,
+            ..Default::default()
+        };
+        let default_client = Client::new(service_address.clone());
+        let client = Client::new(service_address).with_config(config);
+
+        async_std::task::block_on(async {
+            let product: f64 = default_client.call("mul", Params([2.5, 3.5])).await.unwrap();
+            assert_eq!(product, 8.75);
+            let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+            assert_eq!(product, 8.75);
+        });
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].lines().any(|line| line == "Accept: application/json"));
+        assert!(!requests[1].lines().any(|line| line.starts_with("Accept:")));
+    }
+
+    #[test]
+    fn test_rpc_error_carries_request_id() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RpcId;
+
+        let server = mul_server();
+        let client = Client::new(server.service_address());
+
+        let error = async_std::task::block_on(async {
+            let _: f64 = client.call("mul", Params([1.0, 2.0])).await.unwrap();
+            client.call::<_, f64>("mul", Params([1.0, 2.0, 3.0])).await.unwrap_err()
+        });
+
+        match error {
+            JsonRpcError::Rpc { code, id, .. } => {
+                assert_eq!(code, -32602);
+                assert_eq!(id, Some(RpcId::from("2")));
+            },
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_request_raw_value_params() {
+        use jsonrpc_v2_client::MockRpcServer;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use serde_json::value::RawValue;
+
+        let raw = RawValue::from_string("[1,2,3]".to_owned()).unwrap();
+        let request = Request::new("sum", Params(raw), "0");
+
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains(r#""params":[1,2,3]"#));
+
+        let escaped = RawValue::from_string(r#"["a\"b"]"#.to_owned()).unwrap();
+        let json = serde_json::to_string(&Request::new("echo", Params(escaped), "0")).unwrap();
+        assert!(json.contains(r#""params":["a\"b"]"#));
+
+        let server = MockRpcServer::new()
+            .handler("sum", |params| {
+                Ok(serde_json::json!(params.as_array().unwrap().iter().map(|x| x.as_i64().unwrap()).sum::<i64>()))
+            })
+            .start();
+        let response = request.send(&server.service_address(), None);
+        assert_eq!(response["result"], 6);
+        assert!(server.requests()[0].contains(r#""params": [1,2,3]"#));
+    }
+
+    #[test]
+    fn test_client_custom_resolver_and_dns_cache() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let server = mul_server();
+        let server_address = server.address();
+        let lookups = Arc::new(Mutex::new(Vec::new()));
+        let connects = Arc::new(AtomicUsize::new(0));
+
+        let recorded = lookups.clone();
+        let counter = connects.clone();
+        // no pooling, so every call has to connect and resolve
+        let config = ClientConfig {
+            max_idle_connections: 0,
+            ..Default::default()
+        };
+        let client = Client::new(ServiceAddress::new("rpc.test:8082", "/api"))
+            .with_config(config)
+            .with_resolver(move |host| {
+                recorded.lock().unwrap().push(host.to_owned());
+                Ok(vec![server_address])
+            })
+            .on_connect(move |peer| {
+                assert_eq!(peer, server_address);
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+
+        async_std::task::block_on(async {
+            for _ in 0..2 {
+                let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+                assert_eq!(product, 8.75);
+            }
+        });
+
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+        assert_eq!(*lookups.lock().unwrap(), ["rpc.test:8082"]);
+        assert!(server.requests()[0].contains("\r\nHost: rpc.test:8082\r\n"));
+    }
+
+    #[test]
+    fn test_client_shutdown() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let disconnects = Arc::new(AtomicUsize::new(0));
+
+        let counter = disconnects.clone();
+        let client = Client::new(server.service_address()).on_disconnect(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        async_std::task::block_on(async {
+            let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+            assert_eq!(product, 8.75);
+            assert_eq!(disconnects.load(Ordering::SeqCst), 0);
+
+            client.shutdown().await;
+            assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+
+            let result = client.call::<_, f64>("mul", Params([2.5, 3.5])).await;
+            assert_eq!(result, Err(JsonRpcError::ClientShutdown));
+            assert_eq!(result.unwrap_err().to_string(), "client shut down");
+        });
+
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_params_omitted_positional_named() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::DynRequest;
+        use jsonrpc_v2_client::MockRpcServer;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let json = serde_json::to_string(&Request::new("ping", Params::omitted(), "0")).unwrap();
+        assert_eq!(json, r#"{"jsonrpc":"2.0","method":"ping","id":"0"}"#);
+
+        let json = serde_json::to_string(&Request::new("ping", Params::positional(Vec::<u32>::new()), "0")).unwrap();
+        assert!(json.contains(r#""params":[]"#));
+
+        let json = serde_json::to_string(&Request::new("ping", Params::named(serde_json::Map::new()), "0")).unwrap();
+        assert!(json.contains(r#""params":{}"#));
+
+        let json = serde_json::to_string(&DynRequest::new("ping", serde_json::Value::Null, 1)).unwrap();
+        assert_eq!(json, r#"{"jsonrpc":"2.0","method":"ping","id":1}"#);
+
+        let server = MockRpcServer::new()
+            .handler("ping", |params| Ok(serde_json::json!(params.is_null())))
+            .start();
+        let client = Client::new(server.service_address());
+        let absent: bool = async_std::task::block_on(client.call("ping", Params::omitted())).unwrap();
+        assert!(absent);
+        assert!(!server.requests()[0].contains("params"));
+    }
+
+    #[test]
+    fn test_prelude() {
+        use jsonrpc_v2_client::prelude::*;
+
+        let request = Request::new("mul", Params([2, 3]), "0");
+        let address = ServiceAddress::new("127.0.0.1:8082", "/api");
+        let api_key = APIKey::new("API-KEY", "abcdef12345");
+        let _client = Client::new(address).with_api_key(api_key).with_config(ClientConfig::default());
+        let _id: RpcId = RpcId::from(1);
+        let _error: Option<JsonRpcError> = None;
+
+        assert_eq!(request.method, "mul");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_timed_monotonic() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let server = mul_server();
+        let request = Request::new("mul", Params([6, 7]), "0");
+
+        let (response, timings) = request.send_timed(&server.service_address(), None).unwrap();
+
+        assert_eq!(response["result"], 42.0);
+        assert!(timings.connect <= timings.write);
+        assert!(timings.write <= timings.first_byte);
+        assert!(timings.first_byte <= timings.total);
+    }
+
+    #[test]
+    fn test_client_unwrap_path() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let wrapped = r#"{"data":{"jsonrpc":"2.0","result":42,"id":"1"},"status":"ok"}"#;
+        let (service_address, server) = serve_raw(vec![http_ok(wrapped), http_ok(wrapped)]);
+        let config = ClientConfig {
+            unwrap_path: Some("data".to_owned()),
+            ..Default::default()
+        };
+        let client = Client::new(service_address.clone()).with_config(config);
+        let missing = Client::new(service_address).with_config(ClientConfig {
+            unwrap_path: Some("payload.inner".to_owned()),
+            ..Default::default()
+        });
+
+        async_std::task::block_on(async {
+            let result: u32 = client.call("answer", Params::omitted()).await.unwrap();
+            assert_eq!(result, 42);
+            let error = missing.call::<_, u32>("answer", Params::omitted()).await.unwrap_err();
+            assert!(
+                matches!(&error, JsonRpcError::ResponseError { id: Some(id), .. } if *id == jsonrpc_v2_client::RpcId::from("1")),
+                "{:?}",
+                error
+            );
+        });
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_client_large_body_with_small_write_buffer() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::MockRpcServer;
+        use jsonrpc_v2_client::Params;
+
+        let server = MockRpcServer::new()
+            .handler("len", |params| Ok(serde_json::json!(params[0].as_str().unwrap().len())))
+            .start();
+        let config = ClientConfig {
+            write_buffer_size: 1024,
+            ..Default::default()
+        };
+        let client = Client::new(server.service_address()).with_config(config);
+        let text = "x".repeat(4 * 1024 * 1024);
+
+        let len: usize = async_std::task::block_on(client.call("len", Params([text.as_str()]))).unwrap();
+        assert_eq!(len, text.len());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_request_id_accessors() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let server = mul_server();
+        let mut request = Request::new("mul", Params([2, 4]), "0");
+        assert_eq!(request.id(), "0");
+
+        request.set_id("7");
+        assert_eq!(request.id(), "7");
+
+        let response = request.send(&server.service_address(), None);
+        assert_eq!(response["id"], "7");
+        assert_eq!(response["result"], 8.0);
+    }
+
+    #[test]
+    fn test_client_batch_split_by_max_size() {
+        use jsonrpc_v2_client::Batch;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let client = Client::new(server.service_address());
+        let mut batch = Batch::new().with_max_batch_size(100);
+        for i in 0..250 {
+            assert_eq!(batch.add("mul", Params([i, 2])).unwrap(), i);
+        }
+        batch.add("mul", Params([1])).unwrap();
+
+        let results = async_std::task::block_on(client.send_batch(batch)).unwrap();
+
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(results.len(), 251);
+        for (i, result) in results[..250].iter().enumerate() {
+            assert_eq!(result.as_ref().unwrap(), &serde_json::json!(i as f64 * 2.0));
+        }
+        assert!(matches!(&results[250], Err(jsonrpc_v2_client::JsonRpcError::Rpc { code: -32602, .. })));
+    }
+
+    #[test]
+    fn test_rpc_id_json_forms() {
+        use jsonrpc_v2_client::RpcId;
+
+        for (json, id) in [
+            ("7", RpcId::from(7)),
+            ("-7", RpcId::from(-7)),
+            (r#""seven""#, RpcId::from("seven")),
+            ("null", RpcId::Null),
+        ] {
+            assert_eq!(serde_json::from_str::<RpcId>(json).unwrap(), id);
+            assert_eq!(serde_json::to_string(&id).unwrap(), json);
+        }
+
+        // fractional and exponent forms are rejected, even when integral
+        assert!(serde_json::from_str::<RpcId>("3.0").is_err());
+        assert!(serde_json::from_str::<RpcId>("3.5").is_err());
+        assert!(serde_json::from_str::<RpcId>("1e3").is_err());
+        assert!(serde_json::from_str::<RpcId>("[1]").is_err());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_raw_invalid_body() {
+        let server = mul_server();
+
+        let response = jsonrpc_v2_client::send_raw(&server.service_address(), None, b"{\"method\": ").unwrap();
+
+        let error: serde_json::Value = serde_json::from_slice(&response).unwrap();
+        assert_eq!(error["error"]["code"], -32700);
+        assert!(error["id"].is_null());
+        assert!(server.requests()[0].ends_with("\r\n\r\n{\"method\": "));
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let json = r#"{
+            "timeout": 1.5,
+            "max_idle_connections": 0,
+            "accept_header": null,
+            "unwrap_path": "data"
+        }"#;
+        let config: ClientConfig = serde_json::from_str(json).unwrap();
+
+        assert_eq!(config.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(config.max_idle_connections, 0);
+        assert_eq!(config.accept_header, None);
+        assert_eq!(config.unwrap_path.as_deref(), Some("data"));
+        assert_eq!(config.dns_cache_ttl, ClientConfig::default().dns_cache_ttl);
+
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["timeout"], 1.5);
+        assert_eq!(value["dns_cache_ttl"], 60.0);
+        let again: ClientConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(again.timeout, config.timeout);
+        assert_eq!(again.write_buffer_size, config.write_buffer_size);
+
+        assert!(serde_json::from_str::<ClientConfig>(r#"{"timeout": -1}"#).is_err());
+
+        let address: ServiceAddress = serde_json::from_str(r#"{"url": "127.0.0.1:8082", "endpoint": "/api"}"#).unwrap();
+        assert_eq!(address.url, "127.0.0.1:8082");
+        assert_eq!(address.authorization, None);
+        let address: ServiceAddress = serde_json::from_value(serde_json::to_value(&address).unwrap()).unwrap();
+        assert_eq!(address.endpoint, "/api");
+    }
+
+    #[test]
+    fn test_trace_logs_request_and_response_id() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::IdGenerator;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RpcId;
+
+        struct Traced;
+
+        impl IdGenerator for Traced {
+            fn next_id(&self) -> RpcId {
+                RpcId::from("trace-me")
+            }
+        }
+
+        let logs = capture_logs();
+        let server = mul_server();
+        let client = Client::new(server.service_address()).with_id_generator(Traced);
+        let _: f64 = async_std::task::block_on(client.call("mul", Params([1, 2]))).unwrap();
+
+        let lines: Vec<String> = logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| line.contains(r#"id = "trace-me""#))
+            .cloned()
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"[jsonrpc_v2_client: sending request id = "trace-me"]"#,
+                r#"[jsonrpc_v2_client: received response id = "trace-me"]"#,
+            ]
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_response_without_status_line() {
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let body = r#"{"jsonrpc":"2.0","result":1,"id":"0"}"#;
+        let (service_address, server) = serve_raw(vec![
+            format!("{}\r\nHTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body, body.len()),
+        ]);
+
+        let error = Request::new("one", Params::omitted(), "0")
+            .send_with_meta(&service_address, None)
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            JsonRpcError::ResponseError {
+                message: "malformed HTTP response: no status line".to_owned(),
+                id: None,
+            }
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_error_into_io_error() {
+        use std::io;
+
+        use jsonrpc_v2_client::JsonRpcError;
+
+        let error = io::Error::from(JsonRpcError::ConnectionError("connection refused".to_owned()));
+        assert_eq!(error.kind(), io::ErrorKind::NotConnected);
+        assert_eq!(error.to_string(), "connection error: connection refused");
+
+        assert_eq!(io::Error::from(JsonRpcError::Timeout).kind(), io::ErrorKind::TimedOut);
+        assert_eq!(io::Error::from(JsonRpcError::InvalidUrl("x".to_owned())).kind(), io::ErrorKind::Other);
+
+        let error = io::Error::from(JsonRpcError::Timeout);
+        let source = error.into_inner().unwrap().downcast::<JsonRpcError>().unwrap();
+        assert_eq!(*source, JsonRpcError::Timeout);
+    }
+
+    #[test]
+    fn test_client_default_and_per_request_headers() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let config = ClientConfig {
+            default_headers: vec![
+                ("traceparent".to_owned(), "00-default-01".to_owned()),
+                ("X-Team".to_owned(), "payments".to_owned()),
+            ],
+            ..Default::default()
+        };
+        let client = Client::new(server.service_address()).with_config(config);
+
+        async_std::task::block_on(async {
+            let _: f64 = client.call("mul", Params([1, 2])).await.unwrap();
+            let _: f64 = client
+                .call_with_headers("mul", Params([1, 2]), &[("TraceParent", "00-override-01")])
+                .await
+                .unwrap();
+        });
+
+        let requests = server.requests();
+        let header_lines = |request: &str| -> Vec<String> {
+            request
+                .lines()
+                .filter(|line| line.to_lowercase().starts_with("traceparent") || line.starts_with("X-Team"))
+                .map(str::to_owned)
+                .collect()
+        };
+        assert_eq!(header_lines(&requests[0]), vec!["traceparent: 00-default-01", "X-Team: payments"]);
+        assert_eq!(header_lines(&requests[1]), vec!["X-Team: payments", "TraceParent: 00-override-01"]);
+    }
+
+    #[test]
+    fn test_client_host_override() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let config = ClientConfig {
+            host_override: Some("api.example.com".to_owned()),
+            ..Default::default()
+        };
+        let client = Client::new(server.service_address()).with_config(config);
+
+        let _: f64 = async_std::task::block_on(client.call("mul", Params([1, 2]))).unwrap();
+
+        let request = &server.requests()[0];
+        assert!(request.lines().any(|line| line == "Host: api.example.com"));
+        assert_eq!(request.lines().filter(|line| line.starts_with("Host:")).count(), 1);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_dyn_request_scalar_params_rejected() {
+        use jsonrpc_v2_client::Batch;
+        use jsonrpc_v2_client::DynRequest;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let rejected = JsonRpcError::SerializationError("params must be array or object".to_owned());
+
+        let request = DynRequest::new("mul", serde_json::json!(2), 1);
+        assert_eq!(request.try_send(&server.service_address(), None), Err(rejected.clone()));
+        assert!(server.requests().is_empty());
+
+        assert_eq!(Batch::new().add("mul", Params("2")), Err(rejected));
+
+        let request = DynRequest::new("mul", serde_json::json!([2, 3]), 2);
+        assert_eq!(request.try_send(&server.service_address(), None).unwrap()["result"], 6.0);
+    }
+
+    #[test]
+    fn test_async_api_without_blocking_feature() {
+        use jsonrpc_v2_client::prelude::*;
+
+        let server = mul_server();
+        let client = Client::new(server.service_address());
+
+        let product: f64 = async_std::task::block_on(client.call("mul", Params([3, 5]))).unwrap();
+
+        assert_eq!(product, 15.0);
+    }
+
+    #[cfg(feature = "socks")]
+    #[test]
+    fn test_client_socks5_proxy() {
+        use std::net::TcpStream;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Proxy;
+
+        let backend = mul_server();
+        let backend_address = backend.address().to_string();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_address = listener.local_addr().unwrap().to_string();
+
+        // minimal SOCKS5 server requiring username / password, forwarding one connection
+        let proxy = thread::spawn(move || {
+            let (mut client, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            client.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 2]);
+            client.write_all(&[5, 2]).unwrap();
+
+            let mut header = [0u8; 2];
+            client.read_exact(&mut header).unwrap();
+            let mut username = vec![0u8; header[1] as usize];
+            client.read_exact(&mut username).unwrap();
+            let mut length = [0u8; 1];
+            client.read_exact(&mut length).unwrap();
+            let mut password = vec![0u8; length[0] as usize];
+            client.read_exact(&mut password).unwrap();
+            assert_eq!((username.as_slice(), password.as_slice()), (&b"user"[..], &b"secret"[..]));
+            client.write_all(&[1, 0]).unwrap();
+
+            let mut request = [0u8; 4];
+            client.read_exact(&mut request).unwrap();
+            assert_eq!(request, [5, 1, 0, 1]);
+            let mut target = [0u8; 6];
+            client.read_exact(&mut target).unwrap();
+            let port = u16::from_be_bytes([target[4], target[5]]);
+            let target = format!("{}.{}.{}.{}:{}", target[0], target[1], target[2], target[3], port);
+
+            let mut upstream = TcpStream::connect(&target).unwrap();
+            client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).unwrap();
+
+            let mut client_reader = client.try_clone().unwrap();
+            let mut upstream_writer = upstream.try_clone().unwrap();
+            let forward = thread::spawn(move || {
+                let _ = std::io::copy(&mut client_reader, &mut upstream_writer);
+                let _ = upstream_writer.shutdown(std::net::Shutdown::Both);
+            });
+            let _ = std::io::copy(&mut upstream, &mut client);
+            let _ = forward.join();
+            target
+        });
+
+        let config = ClientConfig {
+            proxy: Some(Proxy::Socks5 {
+                addr: proxy_address,
+                auth: Some(("user".to_owned(), "secret".to_owned())),
+            }),
+            ..Default::default()
+        };
+        let client = Client::new(backend.service_address()).with_config(config);
+
+        async_std::task::block_on(async {
+            let product: f64 = client.call("mul", Params([6, 7])).await.unwrap();
+            assert_eq!(product, 42.0);
+            client.shutdown().await;
+        });
+
+        assert_eq!(proxy.join().unwrap(), backend_address);
+    }
+
+    #[test]
+    fn test_client_batch_duplicate_ids() {
+        use jsonrpc_v2_client::Batch;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::IdGenerator;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RpcId;
+
+        struct Constant;
+
+        impl IdGenerator for Constant {
+            fn next_id(&self) -> RpcId {
+                RpcId::from("1")
+            }
+        }
+
+        let server = mul_server();
+        let client = Client::new(server.service_address()).with_id_generator(Constant);
+        let mut batch = Batch::new();
+        batch.add("mul", Params([1, 2])).unwrap();
+        batch.add("mul", Params([3, 4])).unwrap();
+
+        let error = async_std::task::block_on(client.send_batch(batch)).unwrap_err();
+
+        assert_eq!(error, JsonRpcError::SerializationError("duplicate id in batch".to_owned()));
+        assert!(server.requests().is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_retry_policy_jitter_bounds() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::RetryPolicy;
+
+        let policy = RetryPolicy {
+            jitter: 0.25,
+            seed: Some(1234),
+            max_delay: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let unjittered = RetryPolicy {
+            jitter: 0.0,
+            ..policy.clone()
+        };
+
+        let delays: Vec<Duration> = (0..8).map(|retry| policy.delay(retry)).collect();
+
+        for (retry, delay) in delays.iter().enumerate() {
+            let base = unjittered.delay(retry as u32);
+            assert!(*delay >= base.mul_f64(0.75), "{:?} below bound of {:?}", delay, base);
+            assert!(*delay <= base.mul_f64(1.25), "{:?} above bound of {:?}", delay, base);
+        }
+        assert!(delays.iter().enumerate().any(|(retry, delay)| *delay != unjittered.delay(retry as u32)));
+
+        let again: Vec<Duration> = (0..8).map(|retry| policy.delay(retry)).collect();
+        assert_eq!(delays, again);
+
+        let reseeded = RetryPolicy {
+            seed: Some(4321),
+            ..policy.clone()
+        };
+        assert_ne!(delays, (0..8).map(|retry| reseeded.delay(retry)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_client_error_mapper() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        fn boom_errors(response: &serde_json::Value) -> Option<JsonRpcError> {
+            Some(JsonRpcError::Rpc {
+                code: -32000,
+                message: response.get("err")?.as_str()?.to_owned(),
+                data: None,
+                id: None,
+            })
+        }
+
+        let body = r#"{"err":"boom"}"#;
+        let (service_address, server) = serve_raw(vec![http_ok(body), http_ok(body), http_ok(&mul_handler(
+            &serde_json::json!({"jsonrpc": "2.0", "method": "mul", "params": [2, 3], "id": "2"}),
+        ))]);
+        let plain = Client::new(service_address.clone());
+        let mapped = Client::new(service_address).with_error_mapper(boom_errors);
+
+        async_std::task::block_on(async {
+            let error = plain.call::<_, f64>("mul", Params([2, 3])).await.unwrap_err();
+            assert!(matches!(error, JsonRpcError::IdMismatch { .. }));
+
+            let error = mapped.call::<_, f64>("mul", Params([2, 3])).await.unwrap_err();
+            assert_eq!(
+                error,
+                JsonRpcError::Rpc {
+                    code: -32000,
+                    message: "boom".to_owned(),
+                    data: None,
+                    id: None,
+                }
+            );
+
+            // regular responses still go through default extraction
+            let product: f64 = mapped.call("mul", Params([2, 3])).await.unwrap();
+            assert_eq!(product, 6.0);
+        });
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_params_deserialize() {
+        use jsonrpc_v2_client::Params;
+
+        /// Deserializable only, to show `Serialize` is not required
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let params: Params<Vec<i32>> = serde_json::from_str("[1,2,3]").unwrap();
+        assert_eq!(params.0, vec![1, 2, 3]);
+
+        let params: Params<Point> = serde_json::from_value(serde_json::json!({"x": 1, "y": 2})).unwrap();
+        assert_eq!(params.0, Point { x: 1, y: 2 });
+
+        assert!(serde_json::from_str::<Params<Vec<i32>>>(r#"{"x": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_client_preflight() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let allowed = "HTTP/1.1 204 No Content\r\n\
+            Access-Control-Allow-Methods: OPTIONS, POST\r\n\
+            Access-Control-Allow-Headers: Content-Type\r\n\
+            Content-Length: 0\r\n\r\n";
+        let body = r#"{"jsonrpc":"2.0","result":6,"id":"1"}"#;
+        let (service_address, server) = serve_raw(vec![allowed.to_owned(), http_ok(body)]);
+        let config = ClientConfig {
+            preflight: true,
+            ..Default::default()
+        };
+        let client = Client::new(service_address).with_config(config.clone());
+
+        async_std::task::block_on(async {
+            let product: f64 = client.call("mul", Params([2, 3])).await.unwrap();
+            assert_eq!(product, 6.0);
+        });
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("OPTIONS /api HTTP/1.1\r\n"));
+        assert!(requests[0].contains("Access-Control-Request-Method: POST\r\n"));
+        assert!(requests[0].contains("Access-Control-Request-Headers: content-type\r\n"));
+        assert!(requests[1].starts_with("POST /api HTTP/1.1\r\n"));
+
+        let forbidden = "HTTP/1.1 204 No Content\r\nAccess-Control-Allow-Methods: GET\r\nContent-Length: 0\r\n\r\n";
+        let (service_address, server) = serve_raw(vec![forbidden.to_owned()]);
+        let client = Client::new(service_address).with_config(config);
+
+        let error = async_std::task::block_on(client.call::<_, f64>("mul", Params([2, 3]))).unwrap_err();
+        assert_eq!(error, JsonRpcError::Preflight("POST not allowed".to_owned()));
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_read_idle_timeout() {
+        use std::time::{Duration, Instant};
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        // sends a single byte of the response, then stalls
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_http_request(&mut stream);
+            stream.write_all(b"H").unwrap();
+            thread::sleep(Duration::from_secs(3));
+        });
+
+        let config = ClientConfig {
+            timeout: Some(Duration::from_secs(10)),
+            read_idle_timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let client = Client::new(ServiceAddress::new(&url, "/api")).with_config(config);
+
+        let start = Instant::now();
+        let result = async_std::task::block_on(client.call::<_, f64>("mul", Params([2.5, 3.5])));
+
+        assert_eq!(result, Err(JsonRpcError::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_optional_heartbeat() {
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, server) = serve_raw(vec![
+            http_ok("{}"),
+            http_ok(r#"{"jsonrpc":"2.0","id":"0"}"#),
+            http_ok(r#"{"jsonrpc":"2.0","result":"pong","id":"0"}"#),
+            http_ok(r#"{"jsonrpc":"2.0","id":"1"}"#),
+        ]);
+        let request = Request::new("ping", Params::omitted(), "0");
+
+        assert_eq!(request.send_optional::<String>(&service_address, None), Ok(None));
+        assert_eq!(request.send_optional::<String>(&service_address, None), Ok(None));
+        assert_eq!(
+            request.send_optional::<String>(&service_address, None),
+            Ok(Some("pong".to_owned()))
+        );
+        assert!(matches!(
+            request.send_optional::<String>(&service_address, None),
+            Err(JsonRpcError::IdMismatch { .. })
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_call_over_caller_stream() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        use async_std::io::Cursor;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        /// In-memory stream replaying `input` and recording what is written
+        struct Duplex {
+            input: Cursor<Vec<u8>>,
+            output: Vec<u8>,
+        }
+
+        impl async_std::io::Read for Duplex {
+            fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+                Pin::new(&mut self.input).poll_read(cx, buf)
+            }
+        }
+
+        impl async_std::io::Write for Duplex {
+            fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+                self.output.extend_from_slice(buf);
+                Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let mut stream = Duplex {
+            input: Cursor::new(http_ok(r#"{"jsonrpc":"2.0","result":8.75,"id":"1"}"#).into_bytes()),
+            output: Vec::new(),
+        };
+
+        // nothing listens on this address, only the given stream is used
+        let client = Client::new(ServiceAddress::new("127.0.0.1:1", "/api"));
+        let product: f64 = async_std::task::block_on(client.call_over(&mut stream, "mul", Params([2.5, 3.5]))).unwrap();
+
+        assert_eq!(product, 8.75);
+        let request = String::from_utf8(stream.output).unwrap();
+        assert!(request.starts_with("POST /api HTTP/1.1\r\n"));
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["method"], "mul");
+        assert_eq!(body["id"], "1");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_vec_array_or_single() {
+        use serde::Deserialize;
+
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct User {
+            name: String,
+        }
+
+        let (service_address, server) = serve_raw(vec![
+            http_ok(r#"{"jsonrpc":"2.0","result":[{"name":"alice"},{"name":"bob"}],"id":"0"}"#),
+            http_ok(r#"{"jsonrpc":"2.0","result":{"name":"alice"},"id":"0"}"#),
+        ]);
+        let request = Request::new("lookup", Params(["alice", "bob"]), "0");
+
+        let users: Vec<User> = request.send_vec(&service_address, None).unwrap();
+        assert_eq!(
+            users,
+            vec![User { name: "alice".to_owned() }, User { name: "bob".to_owned() }]
+        );
+
+        let users: Vec<User> = request.send_vec(&service_address, None).unwrap();
+        assert_eq!(users, vec![User { name: "alice".to_owned() }]);
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_with_retry_counts_attempts() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::RetryPolicy;
+
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n".to_owned();
+        let (service_address, server) = serve_raw(vec![
+            unavailable.clone(),
+            unavailable,
+            http_ok(r#"{"jsonrpc": "2.0", "result": 8.75, "id": "0"}"#),
+        ]);
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let request = Request::new("mul", Params([2.5, 3.5]), "0");
+
+        let (product, attempts): (f64, u32) = request
+            .send_with_retry_attempts(&service_address, None, &policy)
+            .unwrap();
+
+        assert_eq!(product, 8.75);
+        assert_eq!(attempts, 3);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn test_hmac_signer_known_vector() {
+        use jsonrpc_v2_client::HmacSha256Signer;
+        use jsonrpc_v2_client::RequestSigner;
+
+        let signer = HmacSha256Signer::new(b"secret");
+        let headers = signer.sign(br#"{"jsonrpc":"2.0","method":"ping","id":"1"}"#, 1700000000);
+
+        assert_eq!(
+            headers,
+            vec![
+                (
+                    "X-Signature".to_owned(),
+                    "a584f95002a93bcef7a6e985a09190372a743d1b3c583cd1aabd24ba79ef4653".to_owned()
+                ),
+                ("X-Timestamp".to_owned(), "1700000000".to_owned()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn test_client_sends_signature_headers() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::HmacSha256Signer;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RequestSigner;
+
+        let (service_address, server) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":"pong","id":"1"}"#)]);
+        let client = Client::new(service_address).with_signer(HmacSha256Signer::new(b"secret"));
+
+        let pong: String = async_std::task::block_on(client.call("ping", Params::omitted())).unwrap();
+        assert_eq!(pong, "pong");
+
+        let request = server.join().unwrap().remove(0);
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|line| line.strip_prefix(name))
+                .unwrap()
+                .to_owned()
+        };
+        let timestamp: u64 = header("X-Timestamp: ").parse().unwrap();
+
+        let expected = HmacSha256Signer::new(b"secret").sign(body.as_bytes(), timestamp);
+        assert_eq!(header("X-Signature: "), expected[0].1);
+    }
+
+    #[test]
+    fn test_notify_reads_no_response() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::RpcMethod;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        struct Log;
+
+        impl RpcMethod for Log {
+            const NAME: &'static str = "log";
+            type Params = [&'static str; 1];
+            type Output = ();
+        }
+
+        // records the request and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_http_request(&mut stream);
+            thread::sleep(Duration::from_millis(200));
+            request
+        });
+
+        let client = Client::new(ServiceAddress::new(&url, "/api"));
+        async_std::task::block_on(client.notify::<Log>(["started"])).unwrap();
+
+        let request = server.join().unwrap();
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body, serde_json::json!({"jsonrpc": "2.0", "method": "log", "params": ["started"]}));
+    }
+
+    #[test]
+    fn test_cancelled_call_does_not_pool_connection() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            // answers once, then stops reading so the next request stalls mid-write
+            let (mut stalled, _) = listener.accept().unwrap();
+            read_http_request(&mut stalled);
+            stalled
+                .write_all(http_ok(r#"{"jsonrpc":"2.0","result":1,"id":"1"}"#).as_bytes())
+                .unwrap();
+
+            let (mut fresh, _) = listener.accept().unwrap();
+            let request = read_http_request(&mut fresh);
+            fresh
+                .write_all(http_ok(r#"{"jsonrpc":"2.0","result":3,"id":"3"}"#).as_bytes())
+                .unwrap();
+            drop(stalled);
+            request
+        });
+
+        let connects = Arc::new(AtomicUsize::new(0));
+        let disconnects = Arc::new(AtomicUsize::new(0));
+        let client = {
+            let connects = connects.clone();
+            let disconnects = disconnects.clone();
+            Client::new(ServiceAddress::new(&url, "/api"))
+                .on_connect(move |_| {
+                    connects.fetch_add(1, Ordering::SeqCst);
+                })
+                .on_disconnect(move |_| {
+                    disconnects.fetch_add(1, Ordering::SeqCst);
+                })
+        };
+
+        let first: u32 = async_std::task::block_on(client.call("one", Params::omitted())).unwrap();
+        assert_eq!(first, 1);
+
+        // too large to fit into socket buffers, the write stalls until cancelled;
+        // boxed since the timeout future is too large for the test thread stack in debug builds
+        let large = "x".repeat(32 * 1024 * 1024);
+        let cancelled = async_std::task::block_on(Box::pin(async_std::future::timeout(
+            Duration::from_millis(200),
+            client.call::<_, u32>("echo", Params([large])),
+        )));
+        assert!(cancelled.is_err());
+        assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+
+        let third: u32 = async_std::task::block_on(client.call("three", Params::omitted())).unwrap();
+        assert_eq!(third, 3);
+
+        assert!(server.join().unwrap().contains("three"));
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_lenient_fields() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let body = r#"{"jsonRpc":"2.0","Result":5,"ID":"1"}"#;
+        let (service_address, server) = serve_raw(vec![http_ok(body), http_ok(body)]);
+
+        let lenient = Client::new(service_address.clone()).with_config(ClientConfig {
+            lenient_fields: true,
+            max_idle_connections: 0,
+            ..Default::default()
+        });
+        let result: u32 = async_std::task::block_on(lenient.call("five", Params::omitted())).unwrap();
+        assert_eq!(result, 5);
+
+        let strict = Client::new(service_address).with_config(ClientConfig {
+            max_idle_connections: 0,
+            ..Default::default()
+        });
+        let result = async_std::task::block_on(strict.call::<_, u32>("five", Params::omitted()));
+        assert!(matches!(result, Err(JsonRpcError::IdMismatch { .. })));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_health_check() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let (service_address, server) =
+            serve_raw(vec!["HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n".to_owned()]);
+        let client = Client::new(service_address);
+
+        let latency = async_std::task::block_on(client.health_check()).unwrap();
+        assert!(latency > std::time::Duration::ZERO);
+        assert!(server.join().unwrap()[0].starts_with("OPTIONS /api HTTP/1.1\r\n"));
+
+        // nothing listens on the port once the listener is dropped
+        let url = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let client = Client::new(ServiceAddress::new(&url, "/api"));
+
+        let result = async_std::task::block_on(client.health_check());
+        assert!(matches!(result, Err(JsonRpcError::ConnectionError(_))));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::parse_duration;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+
+        assert_eq!(parse_duration("1500ms"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2.5s"), Ok(Duration::from_millis(2500)));
+        assert_eq!(parse_duration("0.25"), Ok(Duration::from_millis(250)));
+        assert_eq!(
+            parse_duration("abc"),
+            Err(JsonRpcError::SerializationError("invalid duration: abc".to_owned()))
+        );
+        assert!(parse_duration("-1s").is_err());
+
+        let config: ClientConfig = serde_json::from_str(r#"{"timeout": "1500ms", "dns_cache_ttl": "2.5s"}"#).unwrap();
+        assert_eq!(config.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(config.dns_cache_ttl, Some(Duration::from_millis(2500)));
+        assert!(serde_json::from_str::<ClientConfig>(r#"{"timeout": "abc"}"#).is_err());
+    }
+
+    #[test]
+    fn test_invoke_error_names_method_and_output() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::RpcMethod;
+
+        struct Mul;
+
+        impl RpcMethod for Mul {
+            const NAME: &'static str = "mul";
+            type Params = [f64; 2];
+            type Output = Vec<String>;
+        }
+
+        let (service_address, server) = serve(1, mul_handler);
+        let client = Client::new(service_address);
+
+        let error = async_std::task::block_on(client.invoke::<Mul>([2.5, 3.5])).unwrap_err();
+        match error {
+            JsonRpcError::SerializationError(message) => {
+                assert!(message.contains("`mul`"), "{}", message);
+                assert!(message.contains(std::any::type_name::<Vec<String>>()), "{}", message);
+            },
+            error => panic!("unexpected error {:?}", error),
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_base64_bytes_round_trip() {
+        use jsonrpc_v2_client::Base64Bytes;
+        use jsonrpc_v2_client::Params;
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let params = Params(Base64Bytes(bytes.clone()));
+
+        let json = serde_json::to_value(&params).unwrap();
+        assert!(json.is_string());
+
+        let decoded: Params<Base64Bytes> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.0 .0, bytes);
+
+        let empty: Params<Vec<Base64Bytes>> = serde_json::from_str(r#"[""]"#).unwrap();
+        assert_eq!(empty.0, vec![Base64Bytes(Vec::new())]);
+        assert!(serde_json::from_str::<Base64Bytes>(r#""not base64!""#).is_err());
+    }
+
+    #[test]
+    fn test_http_proxy_absolute_form_target() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Proxy;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let (proxy_address, proxy) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":42,"id":"1"}"#)]);
+        let config = ClientConfig {
+            proxy: Some(Proxy::Http { addr: proxy_address.url }),
+            ..Default::default()
+        };
+        let client = Client::new(ServiceAddress::new("rpc.example:8082", "/api")).with_config(config);
+
+        async_std::task::block_on(async {
+            let result: u32 = client.call("answer", Params::omitted()).await.unwrap();
+            assert_eq!(result, 42);
+        });
+
+        let requests = proxy.join().unwrap();
+        assert!(requests[0].starts_with("POST http://rpc.example:8082/api HTTP/1.1\r\n"));
+        assert!(requests[0].contains("\r\nHost: rpc.example:8082\r\n"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_chunked_gzip_response() {
+        use std::io::Write;
+
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(br#"{"jsonrpc": "2.0", "result": 8.75, "id": "1"}"#)
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+        let (first, second) = compressed.split_at(compressed.len() / 2);
+
+        let mut response = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+            Content-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+        for chunk in [first, second] {
+            response.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            response.extend_from_slice(chunk);
+            response.extend_from_slice(b"\r\n");
+        }
+        response.extend_from_slice(b"0\r\n\r\n");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_address = jsonrpc_v2_client::ServiceAddress::new(
+            &listener.local_addr().unwrap().to_string(),
+            "/api",
+        );
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_http_request(&mut stream);
+            stream.write_all(&response).unwrap();
+        });
+
+        let client = Client::new(service_address);
+        async_std::task::block_on(async {
+            let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+            assert_eq!(product, 8.75);
+        });
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_batch_from_iterator() {
+        use jsonrpc_v2_client::Batch;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let client = Client::new(server.service_address());
+        let calls = vec![("mul", Params([1, 2])), ("mul", Params([3, 4])), ("mul", Params([5, 6]))];
+        let batch: Batch = calls.into_iter().collect();
+        assert_eq!(batch.len(), 3);
+
+        let results = async_std::task::block_on(client.send_batch(batch)).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].as_ref().unwrap(), &serde_json::json!(30.0));
+
+        let request = &server.requests()[0];
+        let body: serde_json::Value = serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        let ids: Vec<&str> = body.as_array().unwrap().iter().map(|call| call["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_result_with_null_error() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, server) =
+            serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":42,"error":null,"id":"1"}"#)]);
+        let client = Client::new(service_address);
+
+        async_std::task::block_on(async {
+            let result: u32 = client.call("answer", Params::omitted()).await.unwrap();
+            assert_eq!(result, 42);
+        });
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_connect_attempts_logged_with_address() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let logs = capture_logs();
+        let server = mul_server();
+        let address = server.service_address().url;
+        let client = Client::new(server.service_address());
+        let _: f64 = async_std::task::block_on(client.call("mul", Params([1, 2]))).unwrap();
+
+        let expected = format!("[jsonrpc_v2_client: connected to {}]", address);
+        assert!(logs.lock().unwrap().contains(&expected));
+    }
+
+    #[test]
+    fn test_call_with_timeout_overrides_config() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_address = jsonrpc_v2_client::ServiceAddress::new(
+            &listener.local_addr().unwrap().to_string(),
+            "/api",
+        );
+        let server = thread::spawn(move || {
+            for id in ["1", "2"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                read_http_request(&mut stream);
+                thread::sleep(Duration::from_millis(300));
+                let body = format!(r#"{{"jsonrpc":"2.0","result":42,"id":"{}"}}"#, id);
+                let _ = stream.write_all(http_ok(&body).as_bytes());
+            }
+        });
+
+        let config = ClientConfig {
+            timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let client = Client::new(service_address).with_config(config);
+
+        async_std::task::block_on(async {
+            let error = client.call::<_, u32>("slow", Params::omitted()).await.unwrap_err();
+            assert_eq!(error, JsonRpcError::Timeout);
+            let result: u32 = client
+                .call_with_timeout("slow", Params::omitted(), Duration::from_secs(5))
+                .await
+                .unwrap();
+            assert_eq!(result, 42);
+        });
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_reads_are_retried() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        use async_std::io::Cursor;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        /// In-memory stream failing every other read with `Interrupted` or `WouldBlock`,
+        /// the latter without scheduling a wake, the client pauses and polls again
+        struct Flaky {
+            input: Cursor<Vec<u8>>,
+            reads: usize,
+        }
+
+        impl async_std::io::Read for Flaky {
+            fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+                self.reads += 1;
+                match self.reads % 4 {
+                    1 => Poll::Ready(Err(std::io::ErrorKind::Interrupted.into())),
+                    3 => Poll::Ready(Err(std::io::ErrorKind::WouldBlock.into())),
+                    // short reads so the response takes several attempts
+                    _ => {
+                        let end = buf.len().min(16);
+                        Pin::new(&mut self.input).poll_read(cx, &mut buf[..end])
+                    }
+                }
+            }
+        }
+
+        impl async_std::io::Write for Flaky {
+            fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+                Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let mut stream = Flaky {
+            input: Cursor::new(http_ok(r#"{"jsonrpc":"2.0","result":8.75,"id":"1"}"#).into_bytes()),
+            reads: 0,
+        };
+
+        let client = Client::new(ServiceAddress::new("127.0.0.1:1", "/api"));
+        let product: f64 = async_std::task::block_on(client.call_over(&mut stream, "mul", Params([2.5, 3.5]))).unwrap();
+
+        assert_eq!(product, 8.75);
+        assert!(stream.reads > 4);
+    }
+
+    #[test]
+    fn test_id_prefix() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let config = ClientConfig {
+            id_prefix: Some("clientA".to_owned()),
+            ..Default::default()
+        };
+        let client = Client::new(server.service_address()).with_config(config);
+
+        async_std::task::block_on(async {
+            for _ in 0..2 {
+                let _: f64 = client.call("mul", Params([1, 2])).await.unwrap();
+            }
+        });
+
+        let ids: Vec<serde_json::Value> = server
+            .requests()
+            .iter()
+            .map(|request| {
+                let body: serde_json::Value = serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+                body["id"].clone()
+            })
+            .collect();
+        assert_eq!(ids, ["clientA-1", "clientA-2"]);
+    }
+
+    #[test]
+    fn test_sansio_decode_needs_more_data_until_complete() {
+        use jsonrpc_v2_client::sansio::{decode_closed_response, decode_response, encode_request, DecodeError};
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let request = Request::new("mul", Params([2, 3]), "7");
+        let encoded = encode_request(&request, &ServiceAddress::new("127.0.0.1:8082", "/api"), &[("X-Trace", "1")]).unwrap();
+        let encoded = String::from_utf8(encoded).unwrap();
+        assert!(encoded.starts_with("POST /api HTTP/1.1\r\n"));
+        assert!(encoded.contains("\r\nX-Trace: 1\r\n"));
+        assert!(encoded.ends_with(&serde_json::to_string_pretty(&request).unwrap()));
+
+        let body = r#"{"jsonrpc":"2.0","result":6,"id":"7"}"#;
+        let chunked = format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            10,
+            &body[..10],
+            body.len() - 10,
+            &body[10..]
+        );
+
+        for response in [http_ok(body), chunked] {
+            // a second response follows, it must not be consumed
+            let received = format!("{}HTTP/1.1 200 OK\r\n", response).into_bytes();
+
+            for end in 0..response.len() {
+                assert_eq!(decode_response(&received[..end]).unwrap_err(), DecodeError::NeedMoreData);
+            }
+
+            let frame = decode_response(&received).unwrap();
+            assert_eq!(frame.status, 200);
+            assert_eq!(frame.body, body.as_bytes());
+            assert_eq!(frame.consumed, response.len());
+            assert!(frame.keep_alive);
+        }
+
+        assert!(matches!(decode_response(b"SSH-2.0"), Err(DecodeError::Invalid(_))));
+
+        let closed = format!("HTTP/1.1 200 OK\r\n\r\n{}", body);
+        assert_eq!(decode_response(closed.as_bytes()).unwrap_err(), DecodeError::NeedMoreData);
+        let frame = decode_closed_response(closed.as_bytes()).unwrap();
+        assert_eq!(frame.body, body.as_bytes());
+        assert!(!frame.keep_alive);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_request_to_json_string() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, server) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":6,"id":"5"}"#)]);
+        let request = Request::new("mul", Params(serde_json::json!({"a": 2, "b": 3})), "5");
+        request.send(&service_address, None);
+
+        let received = server.join().unwrap().remove(0);
+        let (_, sent) = received.split_once("\r\n\r\n").unwrap();
+        let sent: serde_json::Value = serde_json::from_str(sent).unwrap();
+        let string: serde_json::Value = serde_json::from_str(&request.to_json_string().unwrap()).unwrap();
+        assert_eq!(string, sent);
+
+        let omitted = Request::new("ping", Params::omitted(), "6");
+        assert_eq!(omitted.to_json_string().unwrap(), r#"{"jsonrpc":"2.0","method":"ping","id":"6"}"#);
+    }
+
+    #[test]
+    fn test_api_keys_rotate_per_call() {
+        use jsonrpc_v2_client::APIKey;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let server = mul_server();
+        let client = Client::new(server.service_address())
+            .with_api_keys(vec![APIKey::new("API-KEY", "first"), APIKey::new("API-KEY", "second")]);
+
+        async_std::task::block_on(async {
+            for _ in 0..4 {
+                let _: f64 = client.call("mul", Params([1, 2])).await.unwrap();
+            }
+        });
+
+        let keys: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("API-KEY: "))
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect();
+        assert_eq!(keys, ["first", "second", "first", "second"]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_with_status() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let server = mul_server();
+        let (status, response) = Request::new("mul", Params([2, 3]), "0")
+            .send_with_status(&server.service_address(), None)
+            .unwrap();
+
+        assert_eq!(status, 200);
+        assert_eq!(response["result"], 6.0);
+    }
+
+    #[test]
+    fn test_raw_tcp_newline_framing() {
+        use std::io::BufRead;
+
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RawTcpClient;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+                let product = request["params"][0].as_f64().unwrap() * request["params"][1].as_f64().unwrap();
+                let response = serde_json::json!({"jsonrpc": "2.0", "result": product, "id": request["id"]});
+                writer.write_all(format!("{}\n", response).as_bytes()).unwrap();
+                requests.push(line);
+            }
+            requests
+        });
+
+        let client = RawTcpClient::new(&address);
+        async_std::task::block_on(async {
+            let product: f64 = client.call("mul", Params([2.5, 3.5])).await.unwrap();
+            assert_eq!(product, 8.75);
+            let product: f64 = client.call("mul", Params([2, 3])).await.unwrap();
+            assert_eq!(product, 6.0);
+        });
+
+        // both calls share one connection, each request is a single line
+        let requests = server.join().unwrap();
+        assert!(requests.iter().all(|line| line.ends_with("}\n") && !line.starts_with("POST")));
+    }
+
+    #[test]
+    fn test_raw_tcp_content_length_framing() {
+        use std::io::BufRead;
+
+        use jsonrpc_v2_client::Framing;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RawTcpClient;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            let length: usize = header.trim().strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+            let mut separator = String::new();
+            reader.read_line(&mut separator).unwrap();
+            assert_eq!(separator, "\r\n");
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let response = serde_json::json!({"jsonrpc": "2.0", "result": 8.75, "id": request["id"]}).to_string();
+            let message = format!(
+                "Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+                response.len(),
+                response
+            );
+            writer.write_all(message.as_bytes()).unwrap();
+            request
+        });
+
+        let client = RawTcpClient::new(&address).with_framing(Framing::ContentLength);
+        let product: f64 = async_std::task::block_on(client.call("mul", Params([2.5, 3.5]))).unwrap();
+
+        assert_eq!(product, 8.75);
+        assert_eq!(server.join().unwrap()["method"], "mul");
+    }
+
+    #[test]
+    fn test_unresolvable_host_is_resolution_error() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let client = Client::new(ServiceAddress::new("nonexistent.invalid:8082", "/api"));
+        let error = async_std::task::block_on(client.call::<_, f64>("mul", Params([1, 2]))).unwrap_err();
+
+        match error {
+            JsonRpcError::ResolutionError(message) => assert!(message.starts_with("nonexistent.invalid:8082")),
+            error => panic!("expected resolution error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_discover_returns_openrpc_document() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::MockRpcServer;
+
+        let server = MockRpcServer::new()
+            .handler("rpc.discover", |_| {
+                Ok(serde_json::json!({
+                    "openrpc": "1.2.6",
+                    "info": {"title": "math", "version": "1.0.0"},
+                    "methods": [{"name": "mul", "params": [], "result": {"name": "product", "schema": {"type": "number"}}}]
+                }))
+            })
+            .start();
+        let client = Client::new(server.service_address());
+
+        let document = async_std::task::block_on(client.discover()).unwrap();
+
+        assert_eq!(document["openrpc"], "1.2.6");
+        assert_eq!(document["methods"][0]["name"], "mul");
+    }
+
+    #[test]
+    fn test_request_extra_members_flattened() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let mut extra = serde_json::Map::new();
+        extra.insert("meta".to_owned(), serde_json::json!({"tenant": "acme"}));
+        let request = Request::new("mul", Params([2, 3]), "1").with_extra(extra);
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"jsonrpc": "2.0", "method": "mul", "params": [2, 3], "id": "1", "meta": {"tenant": "acme"}})
+        );
+
+        let plain = serde_json::to_value(Request::new("mul", Params([2, 3]), "1")).unwrap();
+        assert!(plain.get("extra").is_none());
+    }
+
+    #[test]
+    fn test_result_validator_rejects_invalid_result() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, server) = serve_raw(vec![
+            http_ok(r#"{"jsonrpc":"2.0","result":"forty-two","id":"1"}"#),
+            http_ok(r#"{"jsonrpc":"2.0","result":42,"id":"2"}"#),
+        ]);
+        let client = Client::new(service_address).with_result_validator(|result| {
+            if result.is_i64() {
+                Ok(())
+            } else {
+                Err(vec![format!("{} is not of type \"integer\"", result)])
+            }
+        });
+
+        async_std::task::block_on(async {
+            let error = client.call::<_, serde_json::Value>("answer", Params::omitted()).await.unwrap_err();
+            assert_eq!(
+                error,
+                JsonRpcError::ResponseError {
+                    message: r#"result failed validation: "forty-two" is not of type "integer""#.to_owned(),
+                    id: Some(jsonrpc_v2_client::RpcId::from("1")),
+                }
+            );
+            let result: u32 = client.call("answer", Params::omitted()).await.unwrap();
+            assert_eq!(result, 42);
+        });
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_max_request_size_rejects_before_connecting() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let service_address = ServiceAddress::new(&listener.local_addr().unwrap().to_string(), "/api");
+        let config = ClientConfig {
+            max_request_size: Some(1024),
+            ..Default::default()
+        };
+        let client = Client::new(service_address).with_config(config);
+
+        let error = async_std::task::block_on(client.call::<_, f64>("sum", Params(vec![1u8; 1024]))).unwrap_err();
+
+        assert_eq!(error, JsonRpcError::SerializationError("request too large".to_owned()));
+        let accepted = listener.accept().map(|_| ()).map_err(|error| error.kind());
+        assert_eq!(accepted, Err(std::io::ErrorKind::WouldBlock));
+    }
+
+    #[test]
+    fn test_service_address_scheme() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Scheme;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let secure = ServiceAddress::from_url("https://x").unwrap();
+        assert_eq!(secure.scheme, Scheme::Https);
+        assert_eq!(secure.url, "x:443");
+
+        let plain = ServiceAddress::new("http://x", "y");
+        assert_eq!(plain.scheme, Scheme::Http);
+        assert_eq!(plain.url, "x:80");
+        assert_eq!(ServiceAddress::from_url("http://x/api").unwrap().scheme, Scheme::Http);
+        assert_eq!(ServiceAddress::new("127.0.0.1:8082", "/api").scheme, Scheme::Http);
+
+        let client = Client::new(secure);
+        let error = async_std::task::block_on(client.call::<_, f64>("mul", Params([1, 2]))).unwrap_err();
+        assert!(matches!(error, JsonRpcError::ConnectionError(message) if message.starts_with("https")));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_optional_no_content() {
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, server) = serve_raw(vec![
+            "HTTP/1.1 204 No Content\r\n\r\n".to_owned(),
+            http_ok(""),
+        ]);
+        let request = Request::new("ping", Params::omitted(), "0");
+
+        assert_eq!(request.send_optional::<String>(&service_address, None), Ok(None));
+        assert_eq!(request.send_optional::<String>(&service_address, None), Ok(None));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_opens_no_socket() {
+        use jsonrpc_v2_client::Batch;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        let config = ClientConfig {
+            dry_run: true,
+            ..Default::default()
+        };
+        let client = Client::new(ServiceAddress::new(&url, "/api")).with_config(config);
+
+        async_std::task::block_on(async {
+            assert_eq!(client.call::<_, Option<f64>>("mul", Params([2, 3])).await, Ok(None));
+            assert_eq!(client.call::<_, serde_json::Value>("mul", Params([2, 3])).await, Ok(serde_json::Value::Null));
+
+            let mut batch = Batch::new();
+            batch.add("mul", Params([2, 3])).unwrap();
+            assert_eq!(client.send_batch(batch).await, Ok(vec![Ok(serde_json::Value::Null)]));
+
+            let mut stream = async_std::io::Cursor::new(Vec::new());
+            assert_eq!(client.call_over::<_, _, Option<f64>>(&mut stream, "mul", Params([2, 3])).await, Ok(None));
+            assert!(stream.into_inner().is_empty());
+        });
+
+        let accepted = listener.accept().map_err(|error| error.kind());
+        assert_eq!(accepted.unwrap_err(), std::io::ErrorKind::WouldBlock);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compress_request_threshold() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let send = |compress_request: bool, compress_request_threshold: usize| {
+            let (service_address, server) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":6,"id":"1"}"#)]);
+            let config = ClientConfig {
+                compress_request,
+                compress_request_threshold,
+                ..Default::default()
+            };
+            let client = Client::new(service_address).with_config(config);
+            let product = async_std::task::block_on(client.call::<_, f64>("mul", Params([2, 3])));
+            assert_eq!(product, Ok(6.0));
+            server.join().unwrap().remove(0)
+        };
+        let content_length = |request: &str| {
+            request
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .map(|value| value.parse::<usize>().unwrap())
+                .unwrap()
+        };
+
+        let uncompressed = send(false, 0);
+        assert!(!uncompressed.contains("Content-Encoding"));
+        let size = content_length(&uncompressed);
+
+        let under = send(true, size + 1);
+        assert!(!under.contains("Content-Encoding"));
+        assert_eq!(content_length(&under), size);
+
+        let over = send(true, size - 1);
+        assert!(over.contains("Content-Encoding: gzip\r\n"));
+        assert_ne!(content_length(&over), size);
+    }
+
+    #[test]
+    fn test_ip_version_filters_resolved_addresses() {
+        use std::net::SocketAddr;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::IpPreference;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let logs = capture_logs();
+        let server = mul_server();
+        let v4: SocketAddr = server.service_address().url.parse().unwrap();
+        let v6 = SocketAddr::from((std::net::Ipv6Addr::LOCALHOST, v4.port()));
+        let client = |ip_version| {
+            let config = ClientConfig {
+                ip_version,
+                ..Default::default()
+            };
+            Client::new(server.service_address())
+                .with_resolver(move |_| Ok(vec![v6, v4]))
+                .with_config(config)
+        };
+
+        let product = async_std::task::block_on(client(IpPreference::V4Only).call::<_, f64>("mul", Params([2, 3])));
+        assert_eq!(product, Ok(6.0));
+        let attempted = format!("connecting to {}", v6);
+        assert!(!logs.lock().unwrap().iter().any(|line| line.contains(&attempted)));
+
+        let error = async_std::task::block_on(client(IpPreference::V6Only).call::<_, f64>("mul", Params([2, 3])));
+        assert!(matches!(error, Err(JsonRpcError::ConnectionError(_))));
+    }
+
+    #[test]
+    fn test_on_large_response_reports_size() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        static REPORTED: Mutex<Vec<(String, usize)>> = Mutex::new(Vec::new());
+
+        fn record(method: &str, bytes: usize) {
+            REPORTED.lock().unwrap().push((method.to_owned(), bytes));
+        }
+
+        let large = format!(r#"{{"jsonrpc":"2.0","result":"{}","id":"1"}}"#, "x".repeat(10_000));
+        let small = r#"{"jsonrpc":"2.0","result":"x","id":"2"}"#;
+        let (service_address, server) = serve_raw(vec![http_ok(&large), http_ok(small)]);
+        let client = Client::new(service_address).on_large_response(4096, record);
+
+        async_std::task::block_on(async {
+            let result: String = client.call("dump", Params::omitted()).await.unwrap();
+            assert_eq!(result.len(), 10_000);
+            let result: String = client.call("echo", Params::omitted()).await.unwrap();
+            assert_eq!(result, "x");
+        });
+
+        assert_eq!(*REPORTED.lock().unwrap(), vec![("dump".to_owned(), large.len())]);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_batch_id_matcher_trims_ids() {
+        use jsonrpc_v2_client::Batch;
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RpcId;
+
+        let body = r#"[{"jsonrpc":"2.0","result":12,"id":" 2 "},{"jsonrpc":"2.0","result":3,"id":"1  "}]"#;
+        let (service_address, server) = serve_raw(vec![http_ok(body), http_ok(body)]);
+        let batch = || vec![("add", Params([1, 2])), ("mul", Params([3, 4]))].into_iter().collect::<Batch>();
+
+        let client = Client::new(service_address.clone());
+        let results = async_std::task::block_on(client.send_batch(batch())).unwrap();
+        let unmatched = |id: &str| {
+            Err(JsonRpcError::ResponseError {
+                message: "no response for request".to_owned(),
+                id: Some(RpcId::from(id)),
+            })
+        };
+        assert_eq!(results, vec![unmatched("1"), unmatched("2")]);
+
+        let trimming = batch().with_id_matcher(|sent, received| match (sent, received.as_str()) {
+            (RpcId::String(sent), Some(received)) => sent == received.trim(),
+            _ => false,
+        });
+        let client = Client::new(service_address);
+        let results = async_std::task::block_on(client.send_batch(trimming)).unwrap();
+        assert_eq!(results, vec![Ok(serde_json::json!(3)), Ok(serde_json::json!(12))]);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_spaces_calls() {
+        use std::time::{Duration, Instant};
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let config: ClientConfig = serde_json::from_str(r#"{"rate_limit": [2, "100ms"]}"#).unwrap();
+        assert_eq!(config.rate_limit, Some((2, Duration::from_millis(100))));
+
+        let server = mul_server();
+        let client = Client::new(server.service_address()).with_config(config);
+        let start = Instant::now();
+        let products: Vec<f64> = async_std::task::block_on(async {
+            let mut products = Vec::new();
+            for i in 0..5 {
+                products.push(client.call("mul", Params([i, 2])).await.unwrap());
+            }
+            products
+        });
+        let elapsed = start.elapsed();
+
+        // two calls from the full bucket, the other three wait 50ms each for a refill
+        assert_eq!(products, vec![0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert!(elapsed >= Duration::from_millis(140), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_error_array_fallback() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let body = r#"["error", -32602, "Invalid params"]"#;
+        let (service_address, server) = serve_raw(vec![http_ok(body), http_ok(body)]);
+        let config = ClientConfig {
+            error_array_fallback: true,
+            ..Default::default()
+        };
+        let strict = Client::new(service_address.clone());
+        let lenient = Client::new(service_address).with_config(config);
+
+        async_std::task::block_on(async {
+            let error = strict.call::<_, f64>("mul", Params([1])).await.unwrap_err();
+            assert!(!matches!(error, JsonRpcError::Rpc { .. }), "{:?}", error);
+
+            let error = lenient.call::<_, f64>("mul", Params([1])).await.unwrap_err();
+            assert_eq!(
+                error,
+                JsonRpcError::Rpc {
+                    code: -32602,
+                    message: "Invalid params".to_owned(),
+                    data: None,
+                    id: Some(jsonrpc_v2_client::RpcId::from("1")),
+                }
+            );
+        });
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_call_with_meta_counts_bytes() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::Params;
+
+        let response = http_ok(r#"{"jsonrpc":"2.0","result":6,"id":"1"}"#);
+        let (service_address, server) = serve_raw(vec![response.clone()]);
+        let client = Client::new(service_address);
+
+        let (product, meta): (f64, _) =
+            async_std::task::block_on(client.call_with_meta("mul", Params([2, 3]))).unwrap();
+        let requests = server.join().unwrap();
+
+        assert_eq!(product, 6.0);
+        assert_eq!(meta.bytes_sent, requests[0].len());
+        assert_eq!(meta.bytes_received, response.len());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_discard() {
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, server) = serve_raw(vec![
+            http_ok(r#"{"jsonrpc":"2.0","result":{"rows":[1,2,3]},"id":"0"}"#),
+            http_ok(r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"disk full"},"id":"0"}"#),
+        ]);
+        let request = Request::new("store", Params(["report"]), "0");
+
+        assert_eq!(request.send_discard(&service_address, None), Ok(()));
+        assert!(matches!(
+            request.send_discard(&service_address, None),
+            Err(JsonRpcError::Rpc { code: -32000, message, .. }) if message == "disk full"
+        ));
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_retry_policy_delay_saturates() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::RetryPolicy;
+
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1000), policy.max_delay);
+        assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+
+        let jittered = RetryPolicy {
+            jitter: 0.5,
+            seed: Some(3),
+            max_delay: Duration::MAX,
+            ..Default::default()
+        };
+        assert_eq!(jittered.delay(1000), Duration::MAX);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_with_retry_caps_retry_after() {
+        use std::time::{Duration, Instant};
+
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::RetryPolicy;
+
+        let (service_address, server) = serve_raw(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 999999999\r\nContent-Length: 0\r\n\r\n".to_owned(),
+            http_ok(r#"{"jsonrpc": "2.0", "result": 8.75, "id": "0"}"#),
+        ]);
+        let policy = RetryPolicy {
+            max_delay: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let request = Request::new("mul", Params([2.5, 3.5]), "0");
+
+        let started = Instant::now();
+        let product: f64 = request.send_with_retry(&service_address, None, &policy).unwrap();
+
+        assert_eq!(product, 8.75);
+        assert!(started.elapsed() < Duration::from_secs(5), "retried after {:?}", started.elapsed());
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_slow_answer_on_pooled_connection_is_not_resent() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_address = jsonrpc_v2_client::ServiceAddress::new(
+            &listener.local_addr().unwrap().to_string(),
+            "/api",
+        );
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_http_request(&mut stream);
+            stream.write_all(http_ok(r#"{"jsonrpc":"2.0","result":6,"id":"1"}"#).as_bytes()).unwrap();
+
+            read_http_request(&mut stream);
+            thread::sleep(Duration::from_millis(300));
+            let _ = stream.write_all(http_ok(r#"{"jsonrpc":"2.0","result":6,"id":"2"}"#).as_bytes());
+
+            // a resent request would arrive on a new connection
+            listener.set_nonblocking(true).unwrap();
+            thread::sleep(Duration::from_millis(200));
+            listener.accept().map(|_| ()).map_err(|error| error.kind())
+        });
+
+        let config = ClientConfig {
+            read_idle_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let client = Client::new(service_address).with_config(config);
+
+        async_std::task::block_on(async {
+            assert_eq!(client.call::<_, f64>("mul", Params([2, 3])).await, Ok(6.0));
+            assert_eq!(client.call::<_, f64>("mul", Params([2, 3])).await, Err(JsonRpcError::Timeout));
+        });
+
+        assert_eq!(server.join().unwrap(), Err(std::io::ErrorKind::WouldBlock));
+    }
+
+    #[cfg(all(feature = "hmac", feature = "gzip"))]
+    #[test]
+    fn test_signature_covers_compressed_body() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::HmacSha256Signer;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RequestSigner;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_address = jsonrpc_v2_client::ServiceAddress::new(
+            &listener.local_addr().unwrap().to_string(),
+            "/api",
+        );
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_http_request_bytes(&mut stream);
+            stream.write_all(http_ok(r#"{"jsonrpc":"2.0","result":"pong","id":"1"}"#).as_bytes()).unwrap();
+            request
+        });
+        let config = ClientConfig {
+            compress_request: true,
+            compress_request_threshold: 0,
+            ..Default::default()
+        };
+        let client = Client::new(service_address)
+            .with_config(config)
+            .with_signer(HmacSha256Signer::new(b"secret"));
+
+        let pong: String = async_std::task::block_on(client.call("ping", Params::omitted())).unwrap();
+        assert_eq!(pong, "pong");
+
+        let request = server.join().unwrap();
+        let end = request.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(request[..end].to_vec()).unwrap();
+        let body = &request[end + 4..];
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|line| line.strip_prefix(name))
+                .unwrap()
+                .to_owned()
+        };
+        let timestamp: u64 = header("X-Timestamp: ").parse().unwrap();
+
+        assert_eq!(header("Content-Encoding: "), "gzip");
+        assert!(body.starts_with(&[0x1f, 0x8b]));
+        let expected = HmacSha256Signer::new(b"secret").sign(body, timestamp);
+        assert_eq!(header("X-Signature: "), expected[0].1);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_max_request_size_applies_to_compressed_body() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+
+        let (service_address, server) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":1024,"id":"1"}"#)]);
+        let config = ClientConfig {
+            max_request_size: Some(1024),
+            compress_request: true,
+            compress_request_threshold: 0,
+            ..Default::default()
+        };
+        let client = Client::new(service_address).with_config(config);
+
+        // thousands of bytes as JSON, far fewer once gzipped
+        let sum = async_std::task::block_on(client.call::<_, f64>("sum", Params(vec![1u8; 1024])));
+
+        assert_eq!(sum, Ok(1024.0));
+        let request = server.join().unwrap().remove(0);
+        assert!(request.contains("Content-Encoding: gzip\r\n"));
+    }
+
+    #[test]
+    fn test_dry_run_typed_outputs() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let client = |dry_run_result: serde_json::Value| {
+            let config = ClientConfig {
+                dry_run: true,
+                dry_run_result,
+                ..Default::default()
+            };
+            Client::new(ServiceAddress::new("127.0.0.1:9", "/api")).with_config(config)
+        };
+        let default = client(serde_json::Value::Null);
+        let supplied = client(serde_json::json!(6.0));
+
+        async_std::task::block_on(async {
+            assert_eq!(default.call::<_, Option<f64>>("mul", Params([2, 3])).await, Ok(None));
+            assert_eq!(default.call::<_, ()>("mul", Params([2, 3])).await, Ok(()));
+            assert_eq!(default.call::<_, serde_json::Value>("mul", Params([2, 3])).await, Ok(serde_json::Value::Null));
+            assert!(default.call::<_, f64>("mul", Params([2, 3])).await.is_err());
+            assert_eq!(supplied.call::<_, f64>("mul", Params([2, 3])).await, Ok(6.0));
+        });
+    }
+
+    #[test]
+    fn test_service_address_new_adds_default_port() {
+        use jsonrpc_v2_client::ServiceAddress;
+
+        assert_eq!(ServiceAddress::new("http://rpc.example", "/api").url, "rpc.example:80");
+        assert_eq!(ServiceAddress::new("https://rpc.example", "/api").url, "rpc.example:443");
+        assert_eq!(ServiceAddress::new("http://rpc.example:8082", "/api").url, "rpc.example:8082");
+        assert_eq!(ServiceAddress::new("http://[::1]", "/api").url, "[::1]:80");
+        assert_eq!(ServiceAddress::new("http://[::1]:8082", "/api").url, "[::1]:8082");
+        assert_eq!(ServiceAddress::new("127.0.0.1:8082", "/api").url, "127.0.0.1:8082");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_with_retry_times_out_on_stalled_server() {
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::RetryPolicy;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        // accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let service_address = ServiceAddress::new(&listener.local_addr().unwrap().to_string(), "/api");
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            initial_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+
+        let request = Request::new("mul", Params([2, 3]), "0").with_timeout(Some(Duration::from_millis(100)));
+        let result = request.send_with_retry::<f64>(&service_address, None, &policy);
+
+        assert_eq!(result, Err(JsonRpcError::Timeout));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_response_error_carries_request_id() {
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::RpcId;
+
+        let (service_address, server) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","id":"1"}"#)]);
+        let client = Client::new(service_address);
+
+        let error = async_std::task::block_on(client.call::<_, f64>("mul", Params([2, 3]))).unwrap_err();
+
+        assert_eq!(
+            error,
+            JsonRpcError::ResponseError {
+                message: "response contains neither result nor error".to_owned(),
+                id: Some(RpcId::from("1")),
+            }
+        );
+        assert_eq!(error.to_string(), r#"response error (request "1"): response contains neither result nor error"#);
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_request_unresolvable_host() {
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        let service_address = ServiceAddress::new("nonexistent.invalid:8082", "/api");
+        let error = Request::new("mul", Params([1, 2]), "0")
+            .send_with_meta(&service_address, None)
+            .unwrap_err();
+
+        match error {
+            JsonRpcError::ResolutionError(message) => assert!(message.starts_with("nonexistent.invalid:8082")),
+            error => panic!("expected resolution error, got {:?}", error),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_sansio_encode_matches_sent_bytes() {
+        use jsonrpc_v2_client::sansio::encode_request;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::Request;
+
+        let (service_address, server) = serve_raw(vec![http_ok(r#"{"jsonrpc":"2.0","result":6,"id":"7"}"#)]);
+        let request = Request::new("mul", Params([2, 3]), "7");
+        let encoded = encode_request(&request, &service_address, &[]).unwrap();
+
+        request.send(&service_address, None);
+
+        assert_eq!(server.join().unwrap(), vec![String::from_utf8(encoded).unwrap()]);
+    }
+
+    #[test]
+    fn test_would_block_reads_back_off() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+        use std::time::Duration;
+
+        use jsonrpc_v2_client::Client;
+        use jsonrpc_v2_client::ClientConfig;
+        use jsonrpc_v2_client::JsonRpcError;
+        use jsonrpc_v2_client::Params;
+        use jsonrpc_v2_client::ServiceAddress;
+
+        /// Stream never ready, answering every read with `WouldBlock`
+        struct Blocked {
+            reads: usize,
+        }
+
+        impl async_std::io::Read for Blocked {
+            fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<std::io::Result<usize>> {
+                self.reads += 1;
+                Poll::Ready(Err(std::io::ErrorKind::WouldBlock.into()))
+            }
+        }
+
+        impl async_std::io::Write for Blocked {
+            fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+                Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let mut stream = Blocked { reads: 0 };
+        let config = ClientConfig {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let client = Client::new(ServiceAddress::new("127.0.0.1:1", "/api")).with_config(config);
+        let result = async_std::task::block_on(client.call_over::<_, _, f64>(&mut stream, "mul", Params([2, 3])));
+
+        assert_eq!(result, Err(JsonRpcError::Timeout));
+        // pauses of 1, 2, 4 ... 50ms fit a handful of reads, spinning would make thousands
+        assert!(stream.reads < 20, "{} reads", stream.reads);
+    }
 }